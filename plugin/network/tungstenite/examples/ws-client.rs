@@ -0,0 +1,30 @@
+use clonesure::cc;
+use rua::node::{Ctrlc, StdioNode};
+use rua_tungstenite::client::WsConnector;
+
+/// Run `ws-broadcast` first, then messages from stdin will be sent to the websocket server.
+#[tokio::main]
+pub async fn main() {
+  let stdio = StdioNode::default();
+  let stdout = stdio.handle().clone();
+
+  let ws = WsConnector::new("ws://127.0.0.1:8080")
+    .header("User-Agent", "rua")
+    .timeout_ms(3000)
+    .connect()
+    .await
+    .expect("failed to connect to websocket server")
+    .on_msg(cc!(|@stdout, data| stdout.write(data)))
+    .spawn();
+
+  stdio.on_input(cc!(|@ws, data| ws.write(data))).spawn();
+
+  Ctrlc::default()
+    .on_signal(move || {
+      ws.stop();
+      stdout.stop();
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,49 @@
+use rua::node::Ctrlc;
+use rua_tungstenite::listener::{Accept, Reject, WsListener};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+
+enum Room {
+  Chat,
+  Game,
+}
+
+/// Use `wscat -c "ws://127.0.0.1:8080/chat?token=secret"` to connect to the chat room.
+/// Clients without the token or with an unknown path are rejected.
+#[tokio::main]
+pub async fn main() {
+  let ws = WsListener::bind("127.0.0.1:8080")
+    .on_request(|addr, request| {
+      if request.uri().query() != Some("token=secret") {
+        return Err(Reject::new(StatusCode::UNAUTHORIZED).body("invalid token"));
+      }
+      let room = match request.uri().path() {
+        "/chat" => Room::Chat,
+        "/game" => Room::Game,
+        _ => return Err(Reject::new(StatusCode::NOT_FOUND)),
+      };
+      println!("{} is accepted", addr);
+      Ok(Accept::default().meta(room))
+    })
+    .on_handshake_error(|addr, e| println!("{}: {}", addr, e))
+    .on_new_peer(|ws_node| {
+      let prefix = match ws_node.meta::<Room>() {
+        Some(Room::Chat) => "[chat]",
+        Some(Room::Game) => "[game]",
+        None => unreachable!(),
+      };
+      ws_node
+        .on_msg(move |data| println!("{} {}", prefix, String::from_utf8_lossy(&data)))
+        .spawn();
+    })
+    .spawn()
+    .await
+    .expect("WebSocket listener failed to bind address");
+
+  println!("WebSocket listener is running at ws://127.0.0.1:8080");
+
+  Ctrlc::default()
+    .on_signal(move || ws.stop())
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use rua::model::{GeneralResult, HandleError};
+use tokio::{net::TcpStream, time};
+use tokio_tungstenite::{
+  tungstenite::{
+    client::IntoClientRequest,
+    http::{HeaderName, HeaderValue},
+  },
+  MaybeTlsStream,
+};
+
+use crate::node::WsNode;
+
+pub type WsClientNode = WsNode<MaybeTlsStream<TcpStream>>;
+
+/// Connect to a websocket server with custom request headers and subprotocols.
+///
+/// `wss://` urls require one of the TLS features of this crate, e.g. `native-tls`.
+pub struct WsConnector<'a> {
+  url: &'a str,
+  headers: Vec<(&'a str, &'a str)>,
+  protocols: Vec<&'a str>,
+  write_buffer: usize,
+  timeout_ms: Option<u64>,
+}
+
+impl<'a> WsConnector<'a> {
+  pub fn new(url: &'a str) -> Self {
+    Self {
+      url,
+      headers: Vec::new(),
+      protocols: Vec::new(),
+      write_buffer: 16,
+      timeout_ms: None,
+    }
+  }
+
+  /// Add a header to the upgrade request.
+  pub fn header(mut self, name: &'a str, value: &'a str) -> Self {
+    self.headers.push((name, value));
+    self
+  }
+
+  /// Request a subprotocol. The one selected by the server is available by `WsNode::protocol`.
+  pub fn protocol(mut self, protocol: &'a str) -> Self {
+    self.protocols.push(protocol);
+    self
+  }
+
+  pub fn write_buffer(mut self, buffer: usize) -> Self {
+    self.write_buffer = buffer;
+    self
+  }
+
+  /// Timeout of the tcp connection and the websocket handshake. Default: no timeout.
+  pub fn timeout_ms(mut self, ms: u64) -> Self {
+    self.timeout_ms = Some(ms);
+    self
+  }
+
+  /// Return `Err` if the url or headers are invalid, failed to connect, the handshake failed or timeout.
+  pub async fn connect(self) -> GeneralResult<WsClientNode> {
+    if let Some(timeout_ms) = self.timeout_ms {
+      match time::timeout(Duration::from_millis(timeout_ms), self.inner_connect()).await {
+        Ok(result) => result,
+        Err(_) => Err(Box::new(HandleError::Timeout)),
+      }
+    } else {
+      self.inner_connect().await
+    }
+  }
+
+  async fn inner_connect(self) -> GeneralResult<WsClientNode> {
+    let mut request = self.url.into_client_request()?;
+    for (name, value) in self.headers {
+      request.headers_mut().append(
+        HeaderName::from_bytes(name.as_bytes())?,
+        HeaderValue::from_str(value)?,
+      );
+    }
+    if !self.protocols.is_empty() {
+      request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_str(&self.protocols.join(", "))?,
+      );
+    }
+
+    let uri = request.uri();
+    let host = uri
+      .host()
+      .ok_or("missing host")?
+      .trim_start_matches('[')
+      .trim_end_matches(']')
+      .to_string();
+    let port = uri.port_u16().unwrap_or_else(|| {
+      if uri.scheme_str() == Some("wss") {
+        443
+      } else {
+        80
+      }
+    });
+
+    let socket = TcpStream::connect((host.as_str(), port)).await?;
+    let addr = socket.peer_addr()?;
+    #[cfg(any(
+      feature = "native-tls",
+      feature = "rustls-tls-native-roots",
+      feature = "rustls-tls-webpki-roots"
+    ))]
+    let (ws, response) = tokio_tungstenite::client_async_tls(request, socket).await?;
+    #[cfg(not(any(
+      feature = "native-tls",
+      feature = "rustls-tls-native-roots",
+      feature = "rustls-tls-webpki-roots"
+    )))]
+    let (ws, response) = {
+      if uri.scheme_str() == Some("wss") {
+        return Err("wss requires a TLS feature".into());
+      }
+      tokio_tungstenite::client_async(request, MaybeTlsStream::Plain(socket)).await?
+    };
+    let protocol = response
+      .headers()
+      .get("Sec-WebSocket-Protocol")
+      .and_then(|value| value.to_str().ok())
+      .map(|value| value.to_string());
+
+    Ok(WsNode::new(ws, self.write_buffer, addr).selected_protocol(protocol))
+  }
+}
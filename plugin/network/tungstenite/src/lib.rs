@@ -1,2 +1,4 @@
+pub mod client;
 pub mod listener;
+pub mod model;
 pub mod node;
@@ -1,20 +1,46 @@
+use std::{
+  any::Any,
+  error::Error,
+  fmt::{self, Display},
+  future::Future,
+  net::SocketAddr,
+  sync::Arc,
+  time::Duration,
+};
+
 use rua::{
   go,
-  model::{GeneralResult, HandleBuilder, StopOnlyHandle, StopRx},
+  model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx},
+  node::{Node, Source},
   take, take_mut, take_option_mut,
 };
-use tokio::{net::TcpListener, sync::mpsc};
+use tokio::{
+  net::TcpListener,
+  sync::{mpsc, Semaphore},
+  time,
+};
+use tokio_tungstenite::tungstenite::{
+  self,
+  handshake::server::{ErrorResponse, Request, Response},
+  http::{HeaderValue, StatusCode},
+};
 
 use crate::node::WsNode;
 
 pub struct WsListener<'a> {
   addr: &'a str,
   peer_write_buffer: usize,
+  handshake_timeout_ms: u64,
+  max_pending_handshakes: usize,
   handle: StopOnlyHandle,
   stop_rx: StopRx,
-  peer_handler: Option<Box<dyn FnMut(WsNode) + Send>>,
+  peer_handler: Option<Handler<WsNode>>,
+  handshake_error_handler: Option<Box<dyn FnMut(SocketAddr, HandshakeError) + Send>>,
+  request_handler: Option<Arc<RequestHandler>>,
 }
 
+type RequestHandler = dyn Fn(SocketAddr, &Request) -> Result<Accept, Reject> + Send + Sync;
+
 impl<'a> WsListener<'a> {
   pub fn bind(addr: &'a str) -> Self {
     let (stop_tx, stop_rx) = mpsc::channel(1);
@@ -27,7 +53,11 @@ impl<'a> WsListener<'a> {
         .build_stop_only()
         .unwrap(),
       peer_write_buffer: 16,
+      handshake_timeout_ms: 10_000,
+      max_pending_handshakes: 64,
       peer_handler: None,
+      handshake_error_handler: None,
+      request_handler: None,
     }
   }
 
@@ -36,28 +66,138 @@ impl<'a> WsListener<'a> {
     self
   }
 
+  /// Connections which can't finish the handshake in time will be dropped. Default: 10s.
+  pub fn handshake_timeout_ms(mut self, ms: u64) -> Self {
+    self.handshake_timeout_ms = ms;
+    self
+  }
+
+  /// Max count of concurrent handshakes, new connections won't be accepted until a handshake is done.
+  /// Default: 64.
+  pub fn max_pending_handshakes(mut self, n: usize) -> Self {
+    self.max_pending_handshakes = n.max(1);
+    self
+  }
+
   pub fn on_new_peer(mut self, f: impl FnMut(WsNode) + 'static + Send) -> Self {
-    self.peer_handler = Some(Box::new(f));
+    self.peer_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before handling the next peer.
+  pub fn on_new_peer_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(WsNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.peer_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  /// Inspect the upgrade request, e.g. the path, headers, query or Origin, before accepting a peer.
+  /// Return `Accept` to select a subprotocol and attach metadata to the new `WsNode`,
+  /// or `Reject` to reply with an HTTP error status.
+  ///
+  /// Called concurrently from handshake tasks.
+  pub fn on_request(
+    mut self,
+    f: impl Fn(SocketAddr, &Request) -> Result<Accept, Reject> + 'static + Send + Sync,
+  ) -> Self {
+    self.request_handler = Some(Arc::new(f));
     self
   }
 
+  pub fn on_handshake_error(
+    mut self,
+    f: impl FnMut(SocketAddr, HandshakeError) + 'static + Send,
+  ) -> Self {
+    self.handshake_error_handler = Some(Box::new(f));
+    self
+  }
+
+  pub fn handle(&self) -> &StopOnlyHandle {
+    &self.handle
+  }
+
   /// Return `Err` if bind address failed or mssing peer_handler.
   pub async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
     take_option_mut!(self, peer_handler);
 
     let server = TcpListener::bind(self.addr).await?;
 
-    take!(self, peer_write_buffer);
-    take_mut!(self, stop_rx);
+    take!(
+      self,
+      peer_write_buffer,
+      handshake_timeout_ms,
+      max_pending_handshakes
+    );
+    take_mut!(self, stop_rx, handshake_error_handler);
+    take!(self, request_handler);
 
     // start ws listener
     go! {
+      let (event_tx, mut event_rx) = mpsc::channel(16);
+      let semaphore = Arc::new(Semaphore::new(max_pending_handshakes));
+      let mut permit = None;
+
       loop {
         tokio::select! {
-          Ok((stream, addr)) = server.accept() => {
-            let ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
-            (peer_handler)(WsNode::new(ws_stream, peer_write_buffer, addr));
+          // only accept new connections when there is a free handshake slot
+          p = semaphore.clone().acquire_owned(), if permit.is_none() => {
+            permit = p.ok();
+          }
+          Ok((stream, addr)) = server.accept(), if permit.is_some() => {
+            let permit = permit.take();
+            let event_tx = event_tx.clone();
+            let request_handler = request_handler.clone();
+            // handshake thread
+            go! {
+              let mut decision = None;
+              // the error response type is required by tungstenite
+              #[allow(clippy::result_large_err)]
+              let callback = |request: &Request, response: Response| {
+                let result = match request_handler {
+                  Some(handler) => handler(addr, request),
+                  None => Ok(Accept::default()),
+                };
+                decision = Some(result);
+                match decision.as_mut().unwrap() {
+                  Ok(accept) => accept.apply(response).map_err(|reject| reject.to_response()),
+                  Err(reject) => Err(reject.to_response()),
+                }
+              };
+              let handshake = time::timeout(
+                Duration::from_millis(handshake_timeout_ms),
+                tokio_tungstenite::accept_hdr_async(stream, callback),
+              );
+              let event = match (handshake.await, decision) {
+                (Ok(Ok(ws_stream)), Some(Ok(accept))) => {
+                  let node = WsNode::new(ws_stream, peer_write_buffer, addr)
+                    .selected_protocol(accept.protocol)
+                    .attached_meta(accept.meta);
+                  Event::Peer(Box::new(node))
+                }
+                (Ok(_), Some(Err(reject))) => {
+                  Event::Error(addr, HandshakeError::Rejected(reject.status))
+                }
+                (Ok(Err(e)), _) => Event::Error(addr, HandshakeError::Ws(e)),
+                (Ok(Ok(_)), None) => unreachable!("handshake done without request"),
+                (Err(_), _) => Event::Error(addr, HandshakeError::Timeout),
+              };
+              drop(permit);
+              event_tx.send(event).await.ok();
+            };
           },
+          Some(event) = event_rx.recv() => {
+            match event {
+              Event::Peer(node) => peer_handler.call(*node).await,
+              Event::Error(addr, e) => {
+                if let Some(handler) = handshake_error_handler.as_mut() {
+                  handler(addr, e);
+                }
+              }
+            }
+          }
           Some(payload) = stop_rx.recv() => {
             (payload.callback)(Ok(()));
             break
@@ -69,3 +209,115 @@ impl<'a> WsListener<'a> {
     Ok(self.handle)
   }
 }
+
+impl<'a> Node for WsListener<'a> {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    WsListener::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    WsListener::spawn(self).await
+  }
+}
+
+impl<'a> Source for WsListener<'a> {
+  type Output = WsNode;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(WsNode) + Send + 'static,
+  {
+    self.on_new_peer(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(WsNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_new_peer_async(f)
+  }
+}
+
+enum Event {
+  Peer(Box<WsNode>),
+  Error(SocketAddr, HandshakeError),
+}
+
+/// Accept a handshake in `WsListener::on_request`.
+#[derive(Default)]
+pub struct Accept {
+  protocol: Option<String>,
+  meta: Option<Box<dyn Any + Send>>,
+}
+
+impl Accept {
+  /// Select a subprotocol, which should be one of the protocols requested by the client.
+  pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+    self.protocol = Some(protocol.into());
+    self
+  }
+
+  /// Attach metadata to the new peer, available by `WsNode::meta`.
+  pub fn meta<T: Any + Send>(mut self, meta: T) -> Self {
+    self.meta = Some(Box::new(meta));
+    self
+  }
+
+  fn apply(&self, mut response: Response) -> Result<Response, Reject> {
+    if let Some(protocol) = &self.protocol {
+      let value = HeaderValue::from_str(protocol)
+        .map_err(|_| Reject::new(StatusCode::INTERNAL_SERVER_ERROR))?;
+      response
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", value);
+    }
+    Ok(response)
+  }
+}
+
+/// Reject a handshake in `WsListener::on_request` with an HTTP error response.
+pub struct Reject {
+  status: StatusCode,
+  body: Option<String>,
+}
+
+impl Reject {
+  pub fn new(status: StatusCode) -> Self {
+    Self { status, body: None }
+  }
+
+  /// Set the body of the error response.
+  pub fn body(mut self, body: impl Into<String>) -> Self {
+    self.body = Some(body.into());
+    self
+  }
+
+  fn to_response(&self) -> ErrorResponse {
+    let mut response = ErrorResponse::new(self.body.clone());
+    *response.status_mut() = self.status;
+    response
+  }
+}
+
+#[derive(Debug)]
+pub enum HandshakeError {
+  Timeout,
+  /// Rejected by `WsListener::on_request` with the status.
+  Rejected(StatusCode),
+  Ws(tungstenite::Error),
+}
+
+impl Display for HandshakeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HandshakeError::Timeout => write!(f, "handshake timeout"),
+      HandshakeError::Rejected(status) => write!(f, "handshake rejected: {}", status),
+      HandshakeError::Ws(e) => write!(f, "handshake failed: {}", e),
+    }
+  }
+}
+
+impl Error for HandshakeError {}
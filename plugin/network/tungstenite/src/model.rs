@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use rua::{
+  go,
+  model::{CallbackFn, GeneralResult, Handle, HandleError, OverflowPolicy, Stop, WriteRx, WriteTx},
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_tungstenite::tungstenite::{self, protocol::CloseFrame};
+
+pub use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+pub type MsgTx = WriteTx<WsMessage>;
+pub type MsgRx = WriteRx<WsMessage>;
+pub type CloseTx = Sender<ClosePayload>;
+pub type CloseRx = Receiver<ClosePayload>;
+
+/// A websocket data message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsMessage {
+  Text(String),
+  Binary(Bytes),
+}
+
+impl WsMessage {
+  pub fn is_text(&self) -> bool {
+    matches!(self, WsMessage::Text(_))
+  }
+
+  pub fn is_binary(&self) -> bool {
+    matches!(self, WsMessage::Binary(_))
+  }
+
+  pub fn into_bytes(self) -> Bytes {
+    match self {
+      WsMessage::Text(text) => Bytes::from(text),
+      WsMessage::Binary(data) => data,
+    }
+  }
+}
+
+impl AsRef<[u8]> for WsMessage {
+  fn as_ref(&self) -> &[u8] {
+    match self {
+      WsMessage::Text(text) => text.as_bytes(),
+      WsMessage::Binary(data) => data,
+    }
+  }
+}
+
+/// Why a `WsNode` is closed.
+#[derive(Debug)]
+pub enum WsCloseReason {
+  /// The peer sent a close frame.
+  Closed(Option<CloseFrame>),
+  /// The connection is closed without a close frame.
+  Eof,
+  /// Nothing is received from the peer within the idle timeout.
+  IdleTimeout,
+  /// Stopped by the handle.
+  Stopped,
+  /// Failed to read from or write to the peer, e.g. a protocol violation or a reset connection.
+  /// The error is shared with the callback of the failed write, if any.
+  Error(Arc<tungstenite::Error>),
+}
+
+pub struct ClosePayload {
+  pub frame: CloseFrame,
+  pub callback: CallbackFn,
+}
+
+/// WsHandle can choose the kind of each message.
+/// Messages written by the inner `Handle` are sent as binary by default, see `WsNode::default_text`.
+#[derive(Clone)]
+pub struct WsHandle {
+  handle: Handle,
+  msg_handle: Handle<WsMessage>,
+  close_tx: CloseTx,
+}
+
+impl WsHandle {
+  pub(crate) fn new(handle: Handle, msg_handle: Handle<WsMessage>, close_tx: CloseTx) -> Self {
+    Self {
+      handle,
+      msg_handle,
+      close_tx,
+    }
+  }
+
+  /// Set the timeout of both raw writes and message writes.
+  pub fn set_timeout_ms(&mut self, ms: u64) {
+    self.handle.set_timeout_ms(ms);
+    self.msg_handle.set_timeout_ms(ms);
+  }
+
+  pub fn clear_timeout(&mut self) {
+    self.handle.clear_timeout();
+    self.msg_handle.clear_timeout();
+  }
+
+  /// Set the overflow policy of both raw writes and message writes.
+  pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+    self.handle.set_overflow(policy);
+    self.msg_handle.set_overflow(policy);
+  }
+
+  pub fn overflow(&self) -> OverflowPolicy {
+    self.msg_handle.overflow()
+  }
+
+  /// Count of writes discarded because the node's buffer was full, see `Handle::dropped`.
+  pub fn dropped(&self) -> u64 {
+    self.handle.dropped() + self.msg_handle.dropped()
+  }
+
+  pub fn write_text(&self, text: impl Into<String>) {
+    self.write_msg_then(WsMessage::Text(text.into()), |_| {})
+  }
+
+  pub fn write_text_then<F>(&self, text: impl Into<String>, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.write_msg_then(WsMessage::Text(text.into()), callback)
+  }
+
+  pub fn write_binary(&self, data: Bytes) {
+    self.write_msg_then(WsMessage::Binary(data), |_| {})
+  }
+
+  pub fn write_binary_then<F>(&self, data: Bytes, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.write_msg_then(WsMessage::Binary(data), callback)
+  }
+
+  pub fn write_msg(&self, msg: WsMessage) {
+    self.write_msg_then(msg, |_| {})
+  }
+
+  pub fn write_msg_then<F>(&self, msg: WsMessage, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.msg_handle.write_then(msg, callback)
+  }
+
+  /// Release the connection without a close frame, see `close` for a graceful close.
+  pub fn stop(self) {
+    self.handle.stop()
+  }
+
+  pub fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.handle.stop_then(callback)
+  }
+
+  /// Send a close frame and wait for the peer to reply one before releasing the connection.
+  /// The wait is bounded by `WsNode::close_timeout_ms`.
+  pub fn close(self, code: CloseCode, reason: impl Into<String>) {
+    self.close_then(code, reason, |_| {})
+  }
+
+  pub fn close_then<F>(self, code: CloseCode, reason: impl Into<String>, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    let payload = ClosePayload {
+      frame: CloseFrame {
+        code,
+        reason: reason.into().into(),
+      },
+      callback: Box::new(callback.clone()),
+    };
+    let close_tx = self.close_tx;
+    go! {
+      if close_tx.send(payload).await.is_err() {
+        callback(Err(Box::new(HandleError::ChannelClosed)));
+      }
+    };
+  }
+
+  /// The handle which writes raw bytes, useful with `Broadcaster`.
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+}
+
+impl Stop for WsHandle {
+  fn stop(self) {
+    WsHandle::stop(self)
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    WsHandle::stop_then(self, callback)
+  }
+}
@@ -1,47 +1,155 @@
-use std::net::SocketAddr;
+use std::{any::Any, convert::TryFrom, future::Future, net::SocketAddr, sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
 use rua::{
   go,
-  model::{Handle, HandleBuilder, StopPayload, StopRx, StopTx, WriteRx},
+  model::{GeneralResult, Handle, HandleBuilder, Handler, StopRx, WriteRx},
+  node::{Node, Sink, Source},
   take, take_mut,
 };
-use tokio::{net::TcpStream, sync::mpsc};
-use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+use tokio::{
+  io::{AsyncRead, AsyncWrite},
+  net::TcpStream,
+  sync::mpsc,
+  time::{self, Instant},
+};
+use tokio_tungstenite::{
+  tungstenite::{protocol::CloseFrame, Message, Utf8Bytes},
+  WebSocketStream,
+};
+
+use crate::{
+  client::{WsClientNode, WsConnector},
+  model::{CloseRx, MsgRx, WsCloseReason, WsHandle, WsMessage},
+};
 
-pub struct WsNode {
+/// WsNode works over any transport, e.g. `TcpStream` for server-side peers.
+pub struct WsNode<S = TcpStream> {
   handle: Handle,
-  ws: WebSocketStream<TcpStream>,
+  ws_handle: WsHandle,
+  ws: WebSocketStream<S>,
   addr: SocketAddr,
+  protocol: Option<String>,
+  meta: Option<Box<dyn Any + Send>>,
+  default_text: bool,
   rx: WriteRx,
+  msg_rx: MsgRx,
   stop_rx: StopRx,
-  stop_tx: StopTx,
-  msg_handler: Option<Box<dyn FnMut(Bytes) + Send>>,
+  close_rx: CloseRx,
+  close_timeout_ms: u64,
+  ping_interval_ms: Option<u64>,
+  idle_timeout_ms: Option<u64>,
+  msg_handler: Option<Handler<WsMessage>>,
+  close_handler: Option<Box<dyn FnOnce(WsCloseReason) + Send>>,
 }
 
-impl WsNode {
-  pub fn new(ws: WebSocketStream<TcpStream>, buffer: usize, addr: SocketAddr) -> Self {
+impl<S> WsNode<S>
+where
+  S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  pub fn new(ws: WebSocketStream<S>, buffer: usize, addr: SocketAddr) -> Self {
     let (tx, rx) = mpsc::channel(buffer);
+    let (msg_tx, msg_rx) = mpsc::channel(buffer);
     let (stop_tx, stop_rx) = mpsc::channel(1);
+    let (close_tx, close_rx) = mpsc::channel(1);
+    let msg_handle = HandleBuilder::new()
+      .tx(msg_tx)
+      .stop_tx(stop_tx.clone())
+      .build()
+      .unwrap();
+    let handle = HandleBuilder::default()
+      .tx(tx)
+      .stop_tx(stop_tx)
+      .build()
+      .unwrap();
 
     Self {
       ws,
       rx,
+      msg_rx,
       addr,
+      protocol: None,
+      meta: None,
+      default_text: false,
       stop_rx,
+      close_rx,
+      close_timeout_ms: 3000,
+      ping_interval_ms: None,
+      idle_timeout_ms: None,
       msg_handler: None,
-      handle: HandleBuilder::default()
-        .tx(tx)
-        .stop_tx(stop_tx.clone())
-        .build()
-        .unwrap(),
-      stop_tx,
+      close_handler: None,
+      ws_handle: WsHandle::new(handle.clone(), msg_handle, close_tx),
+      handle,
     }
   }
 
-  pub fn on_msg(mut self, f: impl FnMut(Bytes) + 'static + Send) -> Self {
-    self.msg_handler = Some(Box::new(f));
+  /// Receive the payload of both text and binary messages.
+  pub fn on_msg(mut self, mut f: impl FnMut(Bytes) + 'static + Send) -> Self {
+    self.msg_handler = Some(Handler::new_sync(move |msg: WsMessage| f(msg.into_bytes())));
+    self
+  }
+
+  /// Receive the payload of both text and binary messages.
+  /// The handler is awaited before reading the next message.
+  pub fn on_msg_async<F, Fut>(mut self, mut f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.msg_handler = Some(Handler::new_async(
+      move |msg: WsMessage| f(msg.into_bytes()),
+    ));
+    self
+  }
+
+  /// Receive messages with their kind.
+  pub fn on_message(mut self, f: impl FnMut(WsMessage) + 'static + Send) -> Self {
+    self.msg_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// Receive messages with their kind.
+  /// The handler is awaited before reading the next message, pings are not replied meanwhile.
+  pub fn on_message_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(WsMessage) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.msg_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  /// Called once when the node is closed, with the reason.
+  /// Read and write errors also close the node, see `WsCloseReason::Error`.
+  pub fn on_close(mut self, f: impl FnOnce(WsCloseReason) + 'static + Send) -> Self {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
+  /// Send a ping to the peer periodically, `0` disables it. Default: disabled.
+  pub fn ping_interval_ms(mut self, ms: u64) -> Self {
+    self.ping_interval_ms = Some(ms).filter(|ms| *ms > 0);
+    self
+  }
+
+  /// Close the node if nothing is received from the peer in time, including pongs.
+  /// Usually used with `ping_interval_ms`. Default: disabled.
+  pub fn idle_timeout_ms(mut self, ms: u64) -> Self {
+    self.idle_timeout_ms = Some(ms);
+    self
+  }
+
+  /// How long `WsHandle::close` waits for the peer to reply a close frame. Default: 3s.
+  pub fn close_timeout_ms(mut self, ms: u64) -> Self {
+    self.close_timeout_ms = ms;
+    self
+  }
+
+  /// Send data written by `Handle` as text messages instead of binary messages.
+  /// Writes of invalid UTF-8 data will fail.
+  pub fn default_text(mut self, enable: bool) -> Self {
+    self.default_text = enable;
     self
   }
 
@@ -49,77 +157,204 @@ impl WsNode {
     &self.handle
   }
 
+  pub fn ws_handle(&self) -> &WsHandle {
+    &self.ws_handle
+  }
+
   pub fn addr(&self) -> &SocketAddr {
     &self.addr
   }
 
+  /// The negotiated subprotocol, if any.
+  pub fn protocol(&self) -> Option<&str> {
+    self.protocol.as_deref()
+  }
+
+  pub(crate) fn selected_protocol(mut self, protocol: Option<String>) -> Self {
+    self.protocol = protocol;
+    self
+  }
+
+  pub(crate) fn attached_meta(mut self, meta: Option<Box<dyn Any + Send>>) -> Self {
+    self.meta = meta;
+    self
+  }
+
+  /// The metadata attached by `WsListener::on_request`, if it's a `T`.
+  pub fn meta<T: Any>(&self) -> Option<&T> {
+    self.meta.as_ref().and_then(|meta| meta.downcast_ref())
+  }
+
+  /// Take the metadata attached by `WsListener::on_request`, if it's a `T`.
+  pub fn take_meta<T: Any>(&mut self) -> Option<T> {
+    match self.meta.take()?.downcast() {
+      Ok(meta) => Some(*meta),
+      Err(meta) => {
+        self.meta = Some(meta);
+        None
+      }
+    }
+  }
+
   pub fn spawn(self) -> Handle {
-    take_mut!(self, stop_rx, rx);
-    take!(self, stop_tx);
+    take_mut!(self, stop_rx, close_rx, rx, msg_rx);
+    take!(
+      self,
+      default_text,
+      ping_interval_ms,
+      idle_timeout_ms,
+      close_timeout_ms,
+      close_handler
+    );
     let (reader_stop_tx, mut reader_stop_rx) = mpsc::channel(1);
     let (writer_stop_tx, mut writer_stop_rx) = mpsc::channel(1);
+    let (frame_tx, mut frame_rx) = mpsc::channel::<CloseFrame>(1);
+    let (reason_tx, mut reason_rx) = mpsc::channel(1);
     let (mut writer, mut reader) = self.ws.split();
 
     // stopper thread
     go! {
-      if let Some(payload) = stop_rx.recv().await {
-        reader_stop_tx.send(()).await.ok();
-        writer_stop_tx.send(()).await.ok();
-        (payload.callback)(Ok(()));
+      let (reason, callback) = tokio::select! {
+        Some(payload) = stop_rx.recv() => (WsCloseReason::Stopped, Some(payload.callback)),
+        Some(payload) = close_rx.recv() => {
+          // let the writer send the close frame, then wait for the reply of the peer
+          frame_tx.send(payload.frame).await.ok();
+          time::timeout(Duration::from_millis(close_timeout_ms), reason_rx.recv())
+            .await
+            .ok();
+          (WsCloseReason::Stopped, Some(payload.callback))
+        }
+        Some(reason) = reason_rx.recv() => (reason, None),
+        else => return,
+      };
+      reader_stop_tx.send(()).await.ok();
+      writer_stop_tx.send(()).await.ok();
+      if let Some(close_handler) = close_handler {
+        close_handler(reason);
+      }
+      if let Some(callback) = callback {
+        callback(Ok(()));
       }
-      // else, all stop_tx are dropped, stop_rx is disabled
 
       // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
     };
 
-    // reader thread
-    if let Some(mut msg_handler) = self.msg_handler {
-      go! {
-        loop {
-          tokio::select! {
-            next = reader.next() => {
-              match next {
-                Some(msg) => {
-                  let msg = msg.expect("read websocket error");
-                  if msg.is_close() {
-                    break;
-                  } else {
-                    msg_handler(Bytes::from(msg.into_data()));
+    // reader thread, always running to reply pings and detect close frames
+    let mut msg_handler = self.msg_handler;
+    let writer_reason_tx = reason_tx.clone();
+    go! {
+      let idle_timeout = Duration::from_millis(idle_timeout_ms.unwrap_or_default());
+      let idle = time::sleep(idle_timeout);
+      tokio::pin!(idle);
+
+      let reason = loop {
+        tokio::select! {
+          next = reader.next() => {
+            idle.as_mut().reset(Instant::now() + idle_timeout);
+            match next {
+              Some(Err(e)) => break WsCloseReason::Error(Arc::new(e)),
+              Some(Ok(msg)) => {
+                match msg {
+                  Message::Close(frame) => {
+                    // the close reply is queued by tungstenite and only sent on the next read,
+                    // so keep reading until the connection is closed, with a time bound
+                    let drain = async { while let Some(Ok(_)) = reader.next().await {} };
+                    time::timeout(Duration::from_millis(close_timeout_ms), drain)
+                      .await
+                      .ok();
+                    break WsCloseReason::Closed(frame)
+                  }
+                  // pong replies are sent automatically
+                  Message::Ping(_) | Message::Pong(_) => {}
+                  Message::Text(text) => {
+                    if let Some(msg_handler) = msg_handler.as_mut() {
+                      msg_handler.call(WsMessage::Text(text.to_string())).await;
+                    }
+                  }
+                  msg => {
+                    if let Some(msg_handler) = msg_handler.as_mut() {
+                      msg_handler.call(WsMessage::Binary(msg.into_data())).await;
+                    }
                   }
                 }
-                None => break,
               }
+              None => break WsCloseReason::Eof,
             }
-            Some(()) = reader_stop_rx.recv() => {
-              break
-            }
+          }
+          _ = &mut idle, if idle_timeout_ms.is_some() => {
+            break WsCloseReason::IdleTimeout
+          }
+          Some(()) = reader_stop_rx.recv() => {
+            return
           }
         }
-        // notify writer thread
-        stop_tx.send(StopPayload::default()).await.ok();
       };
-    }
+      // notify stopper thread
+      reason_tx.send(reason).await.ok();
+    };
 
-    // writer thread
+    // writer thread, a write error closes the whole node
+    let reason_tx = writer_reason_tx;
     go! {
+      let ping_interval = Duration::from_millis(ping_interval_ms.unwrap_or(1000));
+      let mut ping = time::interval_at(Instant::now() + ping_interval, ping_interval);
+
       loop {
         tokio::select! {
           Some(()) = writer_stop_rx.recv() => {
             break
           }
-          payload = rx.recv() => {
-            if let Some(payload) = payload {
-              let result =  writer.send(Message::Binary(payload.data.to_vec())).await;
-              if let Err(e) = result {
-                (payload.callback)(Err(Box::new(e)));
-                break
-              } else {
-                (payload.callback)(Ok(()));
+          Some(frame) = frame_rx.recv() => {
+            // nothing can be sent after a close frame
+            if let Err(e) = writer.send(Message::Close(Some(frame))).await {
+              reason_tx.send(WsCloseReason::Error(Arc::new(e))).await.ok();
+            }
+            break
+          }
+          _ = ping.tick(), if ping_interval_ms.is_some() => {
+            if let Err(e) = writer.send(Message::Ping(Bytes::new())).await {
+              reason_tx.send(WsCloseReason::Error(Arc::new(e))).await.ok();
+              break
+            }
+          }
+          Some(payload) = rx.recv() => {
+            let msg = if default_text {
+              match Utf8Bytes::try_from(payload.data) {
+                Ok(text) => Message::Text(text),
+                Err(e) => {
+                  (payload.callback)(Err(Box::new(e)));
+                  continue
+                }
               }
             } else {
+              Message::Binary(payload.data)
+            };
+            let result = writer.send(msg).await;
+            if let Err(e) = result {
+              let e = Arc::new(e);
+              (payload.callback)(Err(Box::new(e.clone())));
+              reason_tx.send(WsCloseReason::Error(e)).await.ok();
               break
+            } else {
+              (payload.callback)(Ok(()));
+            }
+          }
+          Some(payload) = msg_rx.recv() => {
+            let msg = match payload.data {
+              WsMessage::Text(text) => Message::Text(text.into()),
+              WsMessage::Binary(data) => Message::Binary(data),
+            };
+            let result = writer.send(msg).await;
+            if let Err(e) = result {
+              let e = Arc::new(e);
+              (payload.callback)(Err(Box::new(e.clone())));
+              reason_tx.send(WsCloseReason::Error(e)).await.ok();
+              break
+            } else {
+              (payload.callback)(Ok(()));
             }
           }
+          else => break, // all tx are dropped
         }
       }
     };
@@ -127,3 +362,51 @@ impl WsNode {
     self.handle
   }
 }
+
+impl<S> Node for WsNode<S>
+where
+  S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    WsNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(WsNode::spawn(self))
+  }
+}
+
+impl<S> Source for WsNode<S>
+where
+  S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  type Output = WsMessage;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(WsMessage) + Send + 'static,
+  {
+    self.on_message(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(WsMessage) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_message_async(f)
+  }
+}
+
+impl<S> Sink for WsNode<S> where S: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl WsClientNode {
+  /// Connect to a websocket server. Use `WsConnector` to customize the request.
+  ///
+  /// Return `Err` if the url is invalid, failed to connect or the handshake failed.
+  pub async fn connect(url: &str, buffer: usize) -> GeneralResult<Self> {
+    WsConnector::new(url).write_buffer(buffer).connect().await
+  }
+}
@@ -1,8 +1,10 @@
 use bytes::Bytes;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use rua::model::{GeneralResult, HandleBuilder, StopOnlyHandle, StopRx};
-use rua::{go, take, take_mut, take_option};
+use rua::model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx};
+use rua::node::{Node, Source};
+use rua::{go, take, take_mut, take_option_mut};
+use std::future::Future;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time;
@@ -10,7 +12,7 @@ use tokio::time;
 pub struct RandomNode {
   handle: StopOnlyHandle,
   stop_rx: StopRx,
-  msg_handler: Option<Box<dyn Fn(Bytes) + Send>>,
+  msg_handler: Option<Handler<Bytes>>,
   nbyte: usize,
   interval_ms: u64,
 }
@@ -42,8 +44,18 @@ impl RandomNode {
     self
   }
 
-  pub fn on_msg(mut self, f: impl Fn(Bytes) + 'static + Send) -> Self {
-    self.msg_handler = Some(Box::new(f));
+  pub fn on_msg(mut self, f: impl FnMut(Bytes) + 'static + Send) -> Self {
+    self.msg_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before generating the next message.
+  pub fn on_msg_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.msg_handler = Some(Handler::new_async(f));
     self
   }
 
@@ -53,7 +65,7 @@ impl RandomNode {
 
   /// Return `Err` if missing `msg_handler`.
   pub fn spawn(self) -> GeneralResult<StopOnlyHandle> {
-    take_option!(self, msg_handler);
+    take_option_mut!(self, msg_handler);
     take!(self, interval_ms, nbyte);
     take_mut!(self, stop_rx);
 
@@ -65,7 +77,7 @@ impl RandomNode {
             break
           }
           _ = time::sleep(Duration::from_millis(interval_ms)) => {
-            (msg_handler)(random_alphanumeric_bytes(nbyte))
+            msg_handler.call(random_alphanumeric_bytes(nbyte)).await
           }
         }
       }
@@ -75,6 +87,37 @@ impl RandomNode {
   }
 }
 
+impl Node for RandomNode {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    RandomNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    RandomNode::spawn(self)
+  }
+}
+
+impl Source for RandomNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_msg(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_msg_async(f)
+  }
+}
+
 fn random_alphanumeric_bytes(n: usize) -> Bytes {
   thread_rng().sample_iter(&Alphanumeric).take(n).collect()
 }
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use rua::node::{ctrlc::Ctrlc, stdio::StdioNode};
+use tokio::time;
+
+/// Each line is handled after the previous one is done, even if it's typed or piped in early.
+#[tokio::main]
+pub async fn main() {
+  let stdio = StdioNode::default();
+  let handle = stdio.handle().clone();
+
+  stdio
+    .on_input_async({
+      let handle = handle.clone();
+      move |msg| {
+        let handle = handle.clone();
+        async move {
+          // simulate a slow lookup
+          time::sleep(Duration::from_millis(500)).await;
+          handle.write(Bytes::from(format!("{} bytes", msg.len())));
+        }
+      }
+    })
+    .spawn();
+
+  Ctrlc::default()
+    .on_signal(move || handle.stop())
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
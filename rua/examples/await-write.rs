@@ -0,0 +1,38 @@
+use bytes::Bytes;
+use rua::node::{ctrlc::Ctrlc, file::FileNode, stdio::StdioNode};
+
+/// The same as `callback.rs`, but awaits writes and stops instead of using callbacks.
+#[tokio::main]
+pub async fn main() {
+  let file = FileNode::default()
+    .filename("log.txt")
+    .spawn()
+    .await
+    .expect("failed to create file peer");
+
+  let stdio_node = StdioNode::default();
+  let stdio = stdio_node.handle().clone();
+  stdio_node
+    .on_input_async({
+      let (stdio, file) = (stdio.clone(), file.clone());
+      move |msg| {
+        let (stdio, file) = (stdio.clone(), file.clone());
+        async move {
+          match file.send(msg).await {
+            Ok(_) => stdio.write(Bytes::from_static(b"ok")),
+            Err(e) => stdio.write(Bytes::from(format!("err: {}", e))),
+          }
+        }
+      }
+    })
+    .spawn();
+
+  Ctrlc::default()
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+
+  // stop in order
+  file.stop_and_wait().await.ok();
+  stdio.stop_and_wait().await.ok();
+}
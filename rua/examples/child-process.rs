@@ -0,0 +1,38 @@
+use std::process::Stdio;
+
+use clonesure::cc;
+use rua::node::{ctrlc::Ctrlc, stdio::StdioNode, stream::StreamNode};
+use tokio::process::Command;
+
+/// Every line from stdin will be sent to `cat`, and the output of `cat` will be printed to stdout.
+#[tokio::main]
+pub async fn main() {
+  let mut child = Command::new("cat")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+    .expect("failed to spawn child process");
+
+  let stdio = StdioNode::default();
+  let stdout = stdio.handle().clone();
+
+  let cat = StreamNode::from_halves(
+    child.stdout.take().unwrap(),
+    child.stdin.take().unwrap(),
+    16,
+  )
+  .on_input(cc!(|@stdout, data| stdout.write(data)))
+  .spawn();
+
+  stdio.on_input(cc!(|@cat, data| cat.write(data))).spawn();
+
+  Ctrlc::default()
+    .on_signal(move || {
+      cat.stop();
+      stdout.stop();
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
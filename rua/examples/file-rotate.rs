@@ -0,0 +1,47 @@
+use clonesure::cc;
+use rua::node::{
+  ctrlc::Ctrlc,
+  file::{FileNode, RotateInterval, Rotation},
+  stdio::StdioNode,
+};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Rotate `log.txt` daily or when it's larger than 1KB, keeping 3 rotated files.
+///
+/// Use `kill -HUP <pid>` to rotate now on unix.
+#[tokio::main]
+pub async fn main() {
+  let file_node = FileNode::default().filename("log.txt").rotation(
+    Rotation::default()
+      .max_size(1024)
+      .interval(RotateInterval::Daily)
+      .keep(3),
+  );
+  #[cfg(unix)]
+  let rotate = file_node.rotate_handle().clone();
+  let file = file_node.spawn().await.expect("failed to create file peer");
+
+  let stdio = StdioNode::default()
+    .on_input(cc!(|@file, msg| file.write(msg)))
+    .spawn();
+
+  #[cfg(unix)]
+  {
+    let mut hangup = signal(SignalKind::hangup()).expect("failed to listen for sighup");
+    tokio::spawn(async move {
+      while hangup.recv().await.is_some() {
+        rotate.rotate_then(|result| println!("rotated: {:?}", result.map_err(|e| e.to_string())));
+      }
+    });
+  }
+
+  Ctrlc::default()
+    .on_signal(move || {
+      stdio.stop();
+      file.stop()
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,49 @@
+use bytes::Bytes;
+use rua::{
+  model::{GeneralResult, Handle},
+  node::{Ctrlc, Node, Source, StdioNode, TailNode, Ticker},
+};
+
+/// Forward the output of any source to a sink handle.
+async fn forward<N>(node: N, to: Handle) -> GeneralResult<N::Handle>
+where
+  N: Source<Output = Bytes>,
+{
+  node.on_output(move |data| to.write(data)).spawn().await
+}
+
+/// Run `echo hello >> /tmp/rua-generic.txt` to append lines to the tailed file.
+#[tokio::main]
+pub async fn main() {
+  let stdout = Node::spawn(StdioNode::default()).await.unwrap();
+
+  std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open("/tmp/rua-generic.txt")
+    .unwrap();
+  let tail = forward(
+    TailNode::with_file_name("/tmp/rua-generic.txt"),
+    stdout.clone(),
+  )
+  .await
+  .unwrap();
+
+  let ticker = Ticker::with_interval(1000)
+    .on_output({
+      let stdout = stdout.clone();
+      move |n| stdout.write(Bytes::from(format!("tick {}", n)))
+    })
+    .spawn()
+    .unwrap();
+
+  Ctrlc::default()
+    .on_signal(move || {
+      tail.stop();
+      ticker.stop();
+      stdout.stop();
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,57 @@
+use clonesure::cc;
+use rua::{
+  node::{ctrlc::Ctrlc, tcp::TcpListener},
+  typed::{Decoder, Format, TypedHandle},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Request {
+  Add { a: i64, b: i64 },
+  Echo { text: String },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+  Sum { sum: i64 },
+  Echo { text: String },
+  Error { error: String },
+}
+
+/// A json lines server. Requires the `serde` feature.
+///
+/// Use `nc localhost 8080` to connect, then send `{"op":"add","a":1,"b":2}`.
+#[tokio::main]
+pub async fn main() {
+  let tcp = TcpListener::bind("127.0.0.1:8080")
+    .on_new_peer(|node| {
+      let res: TypedHandle<Response> = TypedHandle::new(node.handle().clone(), Format::Json);
+      node
+        .on_input(
+          Decoder::new(Format::Json)
+            .on_error(cc!(|@res, e, _| {
+              res.write(&Response::Error { error: e.to_string() }).ok();
+            }))
+            .handler(cc!(|@res, req| {
+              let response = match req {
+                Request::Add { a, b } => Response::Sum { sum: a + b },
+                Request::Echo { text } => Response::Echo { text },
+              };
+              res.write(&response).ok();
+            })),
+        )
+        .spawn();
+    })
+    .spawn()
+    .await
+    .unwrap();
+
+  Ctrlc::default()
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+
+  tcp.stop();
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use rua::model::{Handle, HandleBuilder, OverflowPolicy};
+use tokio::{sync::mpsc, time};
+
+/// A slow in-memory node which takes 50ms to handle each value.
+fn spawn_slow_node(policy: OverflowPolicy) -> (Handle<u64>, mpsc::UnboundedReceiver<u64>) {
+  let (tx, mut rx) = mpsc::channel(2);
+  let (stop_tx, _stop_rx) = mpsc::channel(1);
+  let (out_tx, out_rx) = mpsc::unbounded_channel();
+  let handle = HandleBuilder::new()
+    .tx(tx)
+    .stop_tx(stop_tx)
+    .overflow(policy)
+    .build()
+    .unwrap();
+
+  tokio::spawn(async move {
+    while let Some(payload) = rx.recv().await {
+      time::sleep(Duration::from_millis(50)).await;
+      out_tx.send(payload.data).ok();
+      (payload.callback)(Ok(()));
+    }
+  });
+
+  (handle, out_rx)
+}
+
+/// Write a value every 10ms to a node which is 5 times slower, with each overflow policy.
+#[tokio::main]
+pub async fn main() {
+  for policy in [
+    OverflowPolicy::Block,
+    OverflowPolicy::FailFast,
+    OverflowPolicy::DropNewest,
+    OverflowPolicy::DropOldest(2),
+    OverflowPolicy::CoalesceLatest,
+  ] {
+    let (mut handle, mut out_rx) = spawn_slow_node(policy);
+    handle.set_timeout_ms(100);
+
+    for i in 0..20 {
+      handle.write(i);
+      time::sleep(Duration::from_millis(10)).await;
+    }
+    // wait for the node to catch up
+    time::sleep(Duration::from_millis(500)).await;
+    let dropped = handle.dropped();
+    drop(handle);
+
+    let mut received = Vec::new();
+    while let Some(i) = out_rx.recv().await {
+      received.push(i);
+    }
+    println!("{:?}: dropped {}, received {:?}", policy, dropped, received);
+  }
+}
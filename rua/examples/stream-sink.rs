@@ -0,0 +1,26 @@
+use bytes::Bytes;
+use futures_util::StreamExt;
+use rua::{
+  model::HandleError,
+  node::{Source, StdioNode, Ticker},
+};
+
+/// Print the first 5 ticks using stream combinators, then stop all nodes.
+#[tokio::main]
+pub async fn main() {
+  let stdout = StdioNode::default().spawn();
+  let (ticker, ticks) = Ticker::with_interval(200)
+    .spawn_stream(16)
+    .await
+    .expect("failed to spawn ticker");
+
+  ticks
+    .take(5)
+    .map(|n| Ok::<_, HandleError>(Bytes::from(format!("tick {}", n))))
+    .forward(stdout.clone().into_sink())
+    .await
+    .expect("stdout is closed");
+
+  ticker.stop();
+  stdout.stop();
+}
@@ -8,6 +8,7 @@ pub async fn main() {
 
   let tcp = TcpListener::bind("127.0.0.1:8080")
     .on_new_peer(cc!(|@mut bc, node| {
+      let addr = *node.addr();
       // new node will be added to the broadcaster
       bc.add_target(
         node
@@ -15,6 +16,7 @@ pub async fn main() {
             // new message will be sent to the broadcaster
             cc!(|@bc, data| bc.write(data))
           )
+          .on_close(move |reason| println!("{} is closed: {:?}", addr, reason))
           .spawn()
       )
     }))
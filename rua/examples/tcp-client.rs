@@ -0,0 +1,26 @@
+use clonesure::cc;
+use rua::node::{ctrlc::Ctrlc, stdio::StdioNode, tcp::TcpNode};
+
+/// Run `tcp-broadcaster` first, or use `nc -l 8080` as the server.
+#[tokio::main]
+pub async fn main() {
+  let stdio = StdioNode::default();
+  let stdout = stdio.handle().clone();
+
+  let tcp = TcpNode::timed_connect("127.0.0.1:8080", 16, 3000)
+    .await
+    .expect("failed to connect to server")
+    .on_input(cc!(|@stdout, data| stdout.write(data)))
+    .spawn();
+
+  stdio.on_input(cc!(|@tcp, data| tcp.write(data))).spawn();
+
+  Ctrlc::default()
+    .on_signal(move || {
+      tcp.stop();
+      stdout.stop();
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
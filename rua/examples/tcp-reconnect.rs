@@ -0,0 +1,28 @@
+use clonesure::cc;
+use rua::node::{ctrlc::Ctrlc, stdio::StdioNode, tcp::ReconnectingTcpNode};
+
+/// Run `tcp-broadcaster` first, then try to restart it while this client is running.
+#[tokio::main]
+pub async fn main() {
+  let stdio = StdioNode::default();
+  let stdout = stdio.handle().clone();
+
+  let tcp = ReconnectingTcpNode::new("127.0.0.1:8080", 16)
+    .connect_timeout_ms(3000)
+    .retry_ms(100, 5000)
+    .on_connect(|addr| println!("connected to {}", addr))
+    .on_disconnect(|reason| println!("disconnected: {:?}, reconnecting", reason))
+    .on_input(cc!(|@stdout, data| stdout.write(data)))
+    .spawn();
+
+  stdio.on_input(cc!(|@tcp, data| tcp.write(data))).spawn();
+
+  Ctrlc::default()
+    .on_signal(move || {
+      tcp.stop();
+      stdout.stop();
+    })
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,29 @@
+use clonesure::cc;
+use rua::node::{ctrlc::Ctrlc, tls::TlsListener};
+
+/// Generate a self-signed certificate first:
+///
+/// `openssl req -x509 -newkey rsa:2048 -nodes -keyout key.pem -out cert.pem -days 365 -subj "/CN=localhost" -addext "subjectAltName=DNS:localhost"`
+///
+/// Then use `openssl s_client -connect localhost:8443 -CAfile cert.pem` to connect to the tls server.
+#[tokio::main]
+pub async fn main() {
+  let tls = TlsListener::bind("127.0.0.1:8443")
+    .cert_file("cert.pem")
+    .key_file("key.pem")
+    .on_new_peer(|node| {
+      let handle = node.handle().clone();
+      node
+        .on_input(cc!(|@handle, data| handle.write(data)))
+        .spawn();
+    })
+    .spawn()
+    .await
+    .expect("failed to start tls listener");
+
+  Ctrlc::default()
+    .on_signal(move || tls.stop())
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,82 @@
+use bytes::Bytes;
+use rua::{
+  model::{Handle, HandleBuilder},
+  node::{broadcast::Broadcaster, ctrlc::Ctrlc, stdio::StdioNode},
+};
+use tokio::sync::mpsc;
+
+#[derive(Clone)]
+struct Line {
+  no: usize,
+  text: String,
+}
+
+/// An in-memory node which receives `Line` values, no encoding is needed.
+/// Only the stdio node at the end of the graph works with bytes.
+fn spawn_worker<F>(stdio: Handle, f: F) -> Handle<Line>
+where
+  F: Fn(Line) -> String + Send + 'static,
+{
+  let (tx, mut rx) = mpsc::channel(16);
+  let (stop_tx, mut stop_rx) = mpsc::channel(1);
+  let handle = HandleBuilder::new()
+    .tx(tx)
+    .stop_tx(stop_tx)
+    .build()
+    .unwrap();
+
+  tokio::spawn(async move {
+    loop {
+      tokio::select! {
+        Some(payload) = stop_rx.recv() => {
+          (payload.callback)(Ok(()));
+          break
+        }
+        Some(payload) = rx.recv() => {
+          stdio.write(Bytes::from(f(payload.data)));
+          (payload.callback)(Ok(()));
+        }
+        else => break,
+      }
+    }
+  });
+
+  handle
+}
+
+/// Each input line is broadcasted as a typed value to several in-memory workers.
+#[tokio::main]
+pub async fn main() {
+  let stdio_node = StdioNode::default();
+  let stdio = stdio_node.handle().clone();
+
+  let mut bc = Broadcaster::<Line>::new();
+  bc.add_target(spawn_worker(stdio.clone(), |line| {
+    format!("#{} upper: {}", line.no, line.text.to_uppercase())
+  }));
+  bc.add_target(spawn_worker(stdio.clone(), |line| {
+    format!("#{} chars: {}", line.no, line.text.chars().count())
+  }));
+
+  let mut no = 0;
+  stdio_node
+    .on_input({
+      let bc = bc.clone();
+      move |data| {
+        no += 1;
+        bc.write(Line {
+          no,
+          text: String::from_utf8_lossy(&data).into_owned(),
+        });
+      }
+    })
+    .spawn();
+
+  Ctrlc::default()
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+
+  bc.stop_all();
+  stdio.stop();
+}
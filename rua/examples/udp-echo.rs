@@ -0,0 +1,21 @@
+use clonesure::cc;
+use rua::node::{ctrlc::Ctrlc, udp::UdpNode};
+
+/// Use `nc -u localhost 8080` to send datagrams to the udp server.
+#[tokio::main]
+pub async fn main() {
+  let node = UdpNode::bind("127.0.0.1:8080");
+  let handle = node.handle().clone();
+
+  let udp = node
+    .on_input(cc!(|@handle, addr, data| handle.write_to(addr, data)))
+    .spawn()
+    .await
+    .expect("failed to bind udp socket");
+
+  Ctrlc::default()
+    .on_signal(move || udp.stop())
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
@@ -0,0 +1,34 @@
+#[cfg(unix)]
+use clonesure::cc;
+#[cfg(unix)]
+use rua::node::{ctrlc::Ctrlc, unix::UnixListener};
+
+/// Use `nc -U /tmp/rua.sock` to connect to the unix socket server.
+#[cfg(unix)]
+#[tokio::main]
+pub async fn main() {
+  let unix = UnixListener::bind("/tmp/rua.sock")
+    .on_new_peer(|node| {
+      if let Some(cred) = node.cred() {
+        println!("new peer: pid={:?} uid={}", cred.pid(), cred.uid());
+      }
+      let handle = node.handle().clone();
+      node
+        .on_input(cc!(|@handle, data| handle.write(data)))
+        .spawn();
+    })
+    .spawn()
+    .await
+    .expect("failed to bind unix socket");
+
+  Ctrlc::default()
+    .on_signal(move || unix.stop())
+    .wait()
+    .await
+    .expect("failed to listen for ctrlc");
+}
+
+#[cfg(not(unix))]
+pub fn main() {
+  eprintln!("unix sockets are only supported on unix");
+}
@@ -0,0 +1,315 @@
+use std::io::{self, ErrorKind};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Default max length of a frame read by nodes, see `Codec::read_frame`.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// Framing used by byte-oriented nodes to split a byte stream into messages and to write messages back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+  /// Frames are terminated by the delimiter, which is not included in the frame.
+  /// If the delimiter is `\n`, a trailing `\r` will also be discarded.
+  /// The last frame before EOF doesn't need to be terminated.
+  Line(u8),
+  /// Each frame is prefixed by its length as a big-endian `u16`.
+  U16Prefixed,
+  /// Each frame is prefixed by its length as a big-endian `u32`.
+  U32Prefixed,
+  /// No framing. Each read yields the bytes currently available, at most the given size.
+  /// Written data is sent as-is. The size must not be 0.
+  Raw(usize),
+  /// Each frame has exactly the given size. Writing data with another size will fail.
+  /// The size must not be 0.
+  Fixed(usize),
+}
+
+impl Default for Codec {
+  fn default() -> Self {
+    Self::lines()
+  }
+}
+
+impl Codec {
+  /// Newline delimited frames.
+  pub fn lines() -> Self {
+    Self::Line(b'\n')
+  }
+
+  /// Read the next frame, which is at most `max_len` bytes long, e.g. `DEFAULT_MAX_FRAME_LEN`.
+  ///
+  /// Return `Ok(None)` if the reader reached EOF at a frame boundary,
+  /// return `Err` with `UnexpectedEof` if the reader reached EOF inside a frame,
+  /// return `Err` with `InvalidData` if the frame is longer than `max_len`,
+  /// return `Err` with `InvalidInput` if the size of `Raw` or `Fixed` is 0.
+  pub async fn read_frame<R>(&self, reader: &mut R, max_len: usize) -> io::Result<Option<Bytes>>
+  where
+    R: AsyncBufRead + Unpin,
+  {
+    self.validate()?;
+    match *self {
+      Codec::Line(delimiter) => {
+        let mut buf = Vec::new();
+        loop {
+          let available = reader.fill_buf().await?;
+          if available.is_empty() {
+            if buf.is_empty() {
+              return Ok(None);
+            }
+            break;
+          }
+          let (len, done) = match available.iter().position(|b| *b == delimiter) {
+            Some(i) => (i + 1, true),
+            None => (available.len(), false),
+          };
+          // the delimiter and `\r` are not counted
+          if buf.len() + len > max_len + 2 {
+            return Err(too_large(max_len));
+          }
+          buf.extend_from_slice(&available[..len]);
+          reader.consume(len);
+          if done {
+            break;
+          }
+        }
+        if buf.last() == Some(&delimiter) {
+          buf.pop();
+          if delimiter == b'\n' && buf.last() == Some(&b'\r') {
+            buf.pop();
+          }
+        }
+        if buf.len() > max_len {
+          return Err(too_large(max_len));
+        }
+        Ok(Some(Bytes::from(buf)))
+      }
+      Codec::U16Prefixed => {
+        if reader.fill_buf().await?.is_empty() {
+          return Ok(None);
+        }
+        let len = reader.read_u16().await? as usize;
+        Self::read_exact(reader, len, max_len).await.map(Some)
+      }
+      Codec::U32Prefixed => {
+        if reader.fill_buf().await?.is_empty() {
+          return Ok(None);
+        }
+        let len = reader.read_u32().await? as usize;
+        Self::read_exact(reader, len, max_len).await.map(Some)
+      }
+      Codec::Raw(max) => {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+          return Ok(None);
+        }
+        let len = available.len().min(max).min(max_len);
+        let data = Bytes::copy_from_slice(&available[..len]);
+        reader.consume(len);
+        Ok(Some(data))
+      }
+      Codec::Fixed(size) => {
+        if reader.fill_buf().await?.is_empty() {
+          return Ok(None);
+        }
+        Self::read_exact(reader, size, max_len).await.map(Some)
+      }
+    }
+  }
+
+  /// Append the framed data to `buf`.
+  ///
+  /// Return `Err` with `InvalidInput` if the data can't be represented by this codec.
+  pub fn encode(&self, data: &[u8], buf: &mut BytesMut) -> io::Result<()> {
+    self.validate()?;
+    match *self {
+      Codec::Line(delimiter) => {
+        buf.reserve(data.len() + 1);
+        buf.put_slice(data);
+        buf.put_u8(delimiter);
+      }
+      Codec::U16Prefixed => {
+        if data.len() > u16::MAX as usize {
+          return Err(invalid_input("frame too large for u16 length prefix"));
+        }
+        buf.reserve(data.len() + 2);
+        buf.put_u16(data.len() as u16);
+        buf.put_slice(data);
+      }
+      Codec::U32Prefixed => {
+        if data.len() > u32::MAX as usize {
+          return Err(invalid_input("frame too large for u32 length prefix"));
+        }
+        buf.reserve(data.len() + 4);
+        buf.put_u32(data.len() as u32);
+        buf.put_slice(data);
+      }
+      Codec::Raw(_) => buf.extend_from_slice(data),
+      Codec::Fixed(size) => {
+        if data.len() != size {
+          return Err(invalid_input("frame size mismatch"));
+        }
+        buf.extend_from_slice(data);
+      }
+    }
+    Ok(())
+  }
+
+  /// Write a frame. The writer is not flushed.
+  pub async fn write_frame<W>(&self, writer: &mut W, data: &[u8]) -> io::Result<()>
+  where
+    W: AsyncWrite + Unpin,
+  {
+    let mut buf = BytesMut::new();
+    self.encode(data, &mut buf)?;
+    writer.write_all(&buf).await
+  }
+
+  /// Return `Err` with `InvalidInput` if the size of `Raw` or `Fixed` is 0,
+  /// which would yield empty frames without consuming the input.
+  fn validate(&self) -> io::Result<()> {
+    match *self {
+      Codec::Raw(0) | Codec::Fixed(0) => Err(invalid_input("frame size must not be 0")),
+      _ => Ok(()),
+    }
+  }
+
+  /// Check the length before allocating, the length may come from the peer.
+  async fn read_exact<R>(reader: &mut R, len: usize, max_len: usize) -> io::Result<Bytes>
+  where
+    R: AsyncBufRead + Unpin,
+  {
+    if len > max_len {
+      return Err(too_large(max_len));
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(Bytes::from(buf))
+  }
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+  io::Error::new(ErrorKind::InvalidInput, msg)
+}
+
+fn too_large(max_len: usize) -> io::Error {
+  io::Error::new(
+    ErrorKind::InvalidData,
+    format!("frame longer than {} bytes", max_len),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn read_all(codec: Codec, mut input: &[u8], max_len: usize) -> io::Result<Vec<Bytes>> {
+    let mut frames = Vec::new();
+    while let Some(frame) = codec.read_frame(&mut input, max_len).await? {
+      frames.push(frame);
+    }
+    Ok(frames)
+  }
+
+  fn encode(codec: Codec, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut buf = BytesMut::new();
+    codec.encode(data, &mut buf)?;
+    Ok(buf.to_vec())
+  }
+
+  #[tokio::test]
+  async fn line() {
+    let frames = read_all(Codec::lines(), b"a\r\nb\n\nc", 16).await.unwrap();
+    assert_eq!(frames, vec!["a", "b", "", "c"]);
+
+    let frames = read_all(Codec::Line(b';'), b"a\r;b;", 16).await.unwrap();
+    assert_eq!(frames, vec!["a\r", "b"]);
+
+    let e = read_all(Codec::lines(), b"0123456789\n", 4)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+    let e = read_all(Codec::lines(), b"0123456789", 4)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+    let frames = read_all(Codec::lines(), b"0123\r\n", 4).await.unwrap();
+    assert_eq!(frames, vec!["0123"]);
+
+    assert_eq!(encode(Codec::lines(), b"a").unwrap(), b"a\n");
+  }
+
+  #[tokio::test]
+  async fn u16_prefixed() {
+    let frames = read_all(Codec::U16Prefixed, b"\0\x02ab\0\0", 16)
+      .await
+      .unwrap();
+    assert_eq!(frames, vec!["ab", ""]);
+
+    let e = read_all(Codec::U16Prefixed, b"\0\x02a", 16)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    let e = read_all(Codec::U16Prefixed, b"\0", 16).await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    let e = read_all(Codec::U16Prefixed, b"\xff\xff", 16)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+    assert_eq!(encode(Codec::U16Prefixed, b"ab").unwrap(), b"\0\x02ab");
+    let e = encode(Codec::U16Prefixed, &vec![0; 0x10000]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+  }
+
+  #[tokio::test]
+  async fn u32_prefixed() {
+    let frames = read_all(Codec::U32Prefixed, b"\0\0\0\x02ab", 16)
+      .await
+      .unwrap();
+    assert_eq!(frames, vec!["ab"]);
+
+    let e = read_all(Codec::U32Prefixed, b"\0\0\0\x02a", 16)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    // must fail before allocating 4 GiB
+    let e = read_all(Codec::U32Prefixed, b"\xff\xff\xff\xff", 16)
+      .await
+      .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+    assert_eq!(encode(Codec::U32Prefixed, b"ab").unwrap(), b"\0\0\0\x02ab");
+  }
+
+  #[tokio::test]
+  async fn raw() {
+    let frames = read_all(Codec::Raw(2), b"abcde", 16).await.unwrap();
+    assert_eq!(frames, vec!["ab", "cd", "e"]);
+    let frames = read_all(Codec::Raw(16), b"abcde", 2).await.unwrap();
+    assert_eq!(frames, vec!["ab", "cd", "e"]);
+
+    let e = read_all(Codec::Raw(0), b"abcde", 16).await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+
+    assert_eq!(encode(Codec::Raw(2), b"abc").unwrap(), b"abc");
+  }
+
+  #[tokio::test]
+  async fn fixed() {
+    let frames = read_all(Codec::Fixed(2), b"abcd", 16).await.unwrap();
+    assert_eq!(frames, vec!["ab", "cd"]);
+
+    let e = read_all(Codec::Fixed(2), b"abc", 16).await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    let e = read_all(Codec::Fixed(4), b"abcd", 2).await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+    let e = read_all(Codec::Fixed(0), b"abcd", 16).await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+
+    let e = encode(Codec::Fixed(2), b"abc").unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    let e = encode(Codec::Fixed(0), b"").unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+  }
+}
@@ -1,3 +1,6 @@
+pub mod codec;
 pub mod model;
 pub mod node;
+#[cfg(feature = "serde")]
+pub mod typed;
 pub mod utils;
@@ -1,12 +1,29 @@
 use std::{
+  collections::VecDeque,
   error::Error,
   fmt::{self, Display},
+  future::Future,
+  io,
+  pin::Pin,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  task::{Context, Poll},
   time::Duration,
 };
 
 use bytes::Bytes;
+use futures_sink::Sink;
 use tokio::{
-  sync::mpsc::{Receiver, Sender},
+  sync::{
+    mpsc::{
+      self,
+      error::{SendError, TrySendError},
+      OwnedPermit, Receiver, Sender, UnboundedReceiver, UnboundedSender,
+    },
+    oneshot,
+  },
   time,
 };
 
@@ -15,18 +32,19 @@ use crate::{clone, go, take, take_option};
 pub type GeneralResult<T> = std::result::Result<T, Box<dyn Error>>;
 
 pub type CallbackFn = Box<dyn Fn(GeneralResult<()>) + Send + Sync>;
-pub type WriteTx = Sender<WritePayload>;
-pub type WriteRx = Receiver<WritePayload>;
+pub type WriteTx<T = Bytes> = Sender<WritePayload<T>>;
+pub type WriteRx<T = Bytes> = Receiver<WritePayload<T>>;
 pub type StopTx = Sender<StopPayload>;
 pub type StopRx = Receiver<StopPayload>;
 
-pub struct WritePayload {
-  pub data: Bytes,
+/// The data is `Bytes` for I/O nodes, in-memory nodes can pass any type.
+pub struct WritePayload<T = Bytes> {
+  pub data: T,
   pub callback: CallbackFn,
 }
 
-impl WritePayload {
-  pub fn with_data(data: Bytes) -> Self {
+impl<T> WritePayload<T> {
+  pub fn with_data(data: T) -> Self {
     Self {
       data,
       callback: Box::new(|_| {}),
@@ -73,15 +91,80 @@ impl Default for StopPayload {
   }
 }
 
-#[derive(Debug, Default)]
-pub struct HandleBuilder {
-  tx: Option<WriteTx>,
+/// Why an I/O node is closed.
+#[derive(Debug)]
+pub enum CloseReason {
+  /// The input reached the end, e.g. the peer closed the connection.
+  Eof,
+  ReadError(io::Error),
+  WriteError(io::Error),
+  /// Stopped by the handle.
+  Stopped,
+}
+
+pub type CloseHandler = Box<dyn FnOnce(CloseReason) + Send>;
+
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Handler of data produced by a source node.
+///
+/// An async handler is awaited before the node produces the next data,
+/// so data is handled in order, and a slow handler slows down the node instead of queueing data.
+pub enum Handler<T> {
+  Sync(Box<dyn FnMut(T) + Send>),
+  Async(Box<dyn FnMut(T) -> BoxFuture + Send>),
+}
+
+impl<T> Handler<T> {
+  pub fn new_sync<F>(f: F) -> Self
+  where
+    F: FnMut(T) + Send + 'static,
+  {
+    Handler::Sync(Box::new(f))
+  }
+
+  pub fn new_async<F, Fut>(mut f: F) -> Self
+  where
+    F: FnMut(T) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    Handler::Async(Box::new(move |data| Box::pin(f(data))))
+  }
+
+  pub async fn call(&mut self, data: T) {
+    match self {
+      Handler::Sync(f) => f(data),
+      Handler::Async(f) => f(data).await,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct HandleBuilder<T = Bytes> {
+  tx: Option<WriteTx<T>>,
   stop_tx: Option<StopTx>,
   timeout_ms: Option<u64>,
+  overflow: OverflowPolicy,
+}
+
+impl Default for HandleBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
-impl HandleBuilder {
-  pub fn tx(mut self, tx: WriteTx) -> Self {
+impl<T> HandleBuilder<T> {
+  /// Use `default` for handles of `Bytes`.
+  pub fn new() -> Self {
+    Self {
+      tx: None,
+      stop_tx: None,
+      timeout_ms: None,
+      overflow: OverflowPolicy::default(),
+    }
+  }
+
+  pub fn tx(mut self, tx: WriteTx<T>) -> Self {
     self.tx = Some(tx);
     self
   }
@@ -96,14 +179,21 @@ impl HandleBuilder {
     self
   }
 
+  /// Default: `OverflowPolicy::Block`.
+  pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+    self.overflow = policy;
+    self
+  }
+
   /// Return `Err` if missing `tx` or `stop_tx`.
-  pub fn build(self) -> GeneralResult<Handle> {
-    take!(self, timeout_ms);
+  pub fn build(self) -> GeneralResult<Handle<T>> {
+    take!(self, timeout_ms, overflow);
     take_option!(self, tx, stop_tx);
     Ok(Handle {
       tx,
       stop_only: StopOnlyHandle { stop_tx },
       timeout_ms,
+      overflow: Arc::new(Overflow::new(overflow)),
     })
   }
 
@@ -114,6 +204,15 @@ impl HandleBuilder {
   }
 }
 
+/// Stop is implemented by all handles which can stop their nodes.
+pub trait Stop {
+  fn stop(self);
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static;
+}
+
 #[derive(Clone)]
 pub struct StopOnlyHandle {
   stop_tx: StopTx,
@@ -140,16 +239,52 @@ impl StopOnlyHandle {
       }
     };
   }
+
+  /// Resolve when the node is stopped.
+  pub async fn stop_and_wait(self) -> Result<(), HandleError> {
+    let (callback, done_rx) = done_callback();
+    self
+      .stop_tx
+      .send(StopPayload::with_callback(callback))
+      .await
+      .map_err(|_| HandleError::ChannelClosed)?;
+    done_rx.await.unwrap_or(Err(HandleError::ChannelClosed))
+  }
 }
 
-#[derive(Clone)]
-pub struct Handle {
-  tx: WriteTx,
+impl Stop for StopOnlyHandle {
+  fn stop(self) {
+    StopOnlyHandle::stop(self)
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    StopOnlyHandle::stop_then(self, callback)
+  }
+}
+
+/// Handle writes `Bytes` to I/O nodes, or any type to in-memory nodes.
+pub struct Handle<T = Bytes> {
+  tx: WriteTx<T>,
   stop_only: StopOnlyHandle,
   timeout_ms: Option<u64>,
+  overflow: Arc<Overflow<T>>,
+}
+
+impl<T> Clone for Handle<T> {
+  fn clone(&self) -> Self {
+    Self {
+      tx: self.tx.clone(),
+      stop_only: self.stop_only.clone(),
+      timeout_ms: self.timeout_ms,
+      overflow: self.overflow.clone(),
+    }
+  }
 }
 
-impl Handle {
+impl<T: Send + 'static> Handle<T> {
   pub fn set_timeout_ms(&mut self, ms: u64) {
     self.timeout_ms = Some(ms);
   }
@@ -158,64 +293,94 @@ impl Handle {
     self.timeout_ms = None
   }
 
+  /// Set what to do when the node's buffer is full.
+  /// Clones of this handle made afterwards share the policy and the drop counter.
+  /// The timeout is only used by `OverflowPolicy::Block`.
+  pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+    self.overflow = Arc::new(Overflow::new(policy));
+  }
+
+  pub fn overflow(&self) -> OverflowPolicy {
+    self.overflow.policy
+  }
+
+  /// Count of writes discarded because the node's buffer was full,
+  /// including timeouts and `BufferFull` failures.
+  pub fn dropped(&self) -> u64 {
+    self.overflow.dropped.load(Ordering::Relaxed)
+  }
+
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn write(&self, data: Bytes) {
-    clone!(self, tx);
-    Self::inner_write(tx, data, self.timeout_ms, |_| {})
+  pub fn write(&self, data: T) {
+    self.inner_write(data, self.timeout_ms, |_| {})
   }
 
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn write_then<F>(&self, data: Bytes, callback: F)
+  pub fn write_then<F>(&self, data: T, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
-    clone!(self, tx);
-    Self::inner_write(tx, data, self.timeout_ms, callback)
+    self.inner_write(data, self.timeout_ms, callback)
   }
 
   /// Override the default timeout.
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn timed_write(&self, data: Bytes, timeout_ms: u64) {
-    clone!(self, tx);
-    Self::inner_write(tx, data, Some(timeout_ms), |_| {})
+  pub fn timed_write(&self, data: T, timeout_ms: u64) {
+    self.inner_write(data, Some(timeout_ms), |_| {})
   }
 
   /// Override the default timeout.
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn timed_write_then<F>(&self, data: Bytes, timeout_ms: u64, callback: F)
+  pub fn timed_write_then<F>(&self, data: T, timeout_ms: u64, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
-    let tx = self.tx.clone();
-    Self::inner_write(tx, data, Some(timeout_ms), callback)
+    self.inner_write(data, Some(timeout_ms), callback)
   }
 
-  fn inner_write<F>(tx: WriteTx, data: Bytes, timeout_ms: Option<u64>, callback: F)
+  fn inner_write<F>(&self, data: T, timeout_ms: Option<u64>, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
-    go! {
-      if let Some(timeout_ms) = timeout_ms {
-        tokio::select! {
-          result = tx.send(WritePayload::with_data(data).callback(callback.clone())) => {
-            if result.is_err(){
-              callback(Err(Box::new(HandleError::ChannelClosed)));
-            }
-          }
-          _ = time::sleep(Duration::from_millis(timeout_ms)) => {
-            callback(Err(Box::new(HandleError::Timeout)));
-          }
-        }
-      } else {
-        // no timeout
-        let result = tx
-          .send(WritePayload::with_data(data).callback(callback.clone()))
-          .await;
-        if result.is_err() {
-          callback(Err(Box::new(HandleError::ChannelClosed)));
-        }
+    self.dispatch(WritePayload::with_data(data).callback(callback), timeout_ms)
+  }
+
+  /// Send the payload according to the overflow policy.
+  /// Errors are passed to the callback of the payload.
+  fn dispatch(&self, payload: WritePayload<T>, timeout_ms: Option<u64>) {
+    let policy = self.overflow.policy;
+    if let OverflowPolicy::DropOldest(_) | OverflowPolicy::CoalesceLatest = policy {
+      return self.overflow.push(&self.tx, payload);
+    }
+
+    let payload = match self.tx.try_send(payload) {
+      Ok(()) => return,
+      Err(TrySendError::Closed(payload)) => {
+        return (payload.callback)(Err(Box::new(HandleError::ChannelClosed)))
       }
+      Err(TrySendError::Full(payload)) => payload,
     };
+    match policy {
+      OverflowPolicy::FailFast => self.overflow.drop_payload(payload, HandleError::BufferFull),
+      OverflowPolicy::DropNewest => self.overflow.drop_payload(payload, HandleError::Dropped),
+      _ => {
+        // wait for buffer space
+        clone!(self, tx, overflow);
+        go! {
+          let reserve = tx.reserve();
+          let result = if let Some(timeout_ms) = timeout_ms {
+            time::timeout(Duration::from_millis(timeout_ms), reserve).await
+          } else {
+            Ok(reserve.await)
+          };
+          match result {
+            Ok(Ok(permit)) => permit.send(payload),
+            Ok(Err(_)) => (payload.callback)(Err(Box::new(HandleError::ChannelClosed))),
+            Err(_) => overflow.drop_payload(payload, HandleError::Timeout),
+          }
+        };
+      }
+    }
   }
 
   pub fn stop(self) {
@@ -229,15 +394,288 @@ impl Handle {
     self.stop_only.stop_then(callback)
   }
 
+  /// Resolve when the data is written by the node, or failed.
+  /// Waiting for buffer space is canceled if timeout, like `write`.
+  pub async fn send(&self, data: T) -> Result<(), HandleError> {
+    let (callback, done_rx) = done_callback();
+    self.dispatch(
+      WritePayload::with_data(data).callback(callback),
+      self.timeout_ms,
+    );
+    // the payload is dropped without callback if the node is stopped
+    done_rx.await.unwrap_or(Err(HandleError::ChannelClosed))
+  }
+
+  /// Resolve when the node is stopped.
+  pub async fn stop_and_wait(self) -> Result<(), HandleError> {
+    self.stop_only.stop_and_wait().await
+  }
+
   pub fn stop_only_handle(&self) -> &StopOnlyHandle {
     &self.stop_only
   }
+
+  /// Convert into a `Sink<T>`, e.g. to `forward` a stream to the node.
+  /// The sink always waits for buffer space, the overflow policy is not used.
+  pub fn into_sink(self) -> HandleSink<T> {
+    let (done_tx, done_rx) = mpsc::unbounded_channel();
+    HandleSink {
+      tx: self.tx,
+      reserve: None,
+      permit: None,
+      in_flight: 0,
+      done_tx,
+      done_rx,
+    }
+  }
+}
+
+impl<T: Send + 'static> Stop for Handle<T> {
+  fn stop(self) {
+    Handle::stop(self)
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    Handle::stop_then(self, callback)
+  }
+}
+
+/// What a handle does with a write when the node's buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+  /// Wait for buffer space, fail with `HandleError::Timeout` if the timeout of the handle is reached.
+  #[default]
+  Block,
+  /// Fail with `HandleError::BufferFull` immediately.
+  FailFast,
+  /// Discard the new write, which fails with `HandleError::Dropped`.
+  DropNewest,
+  /// Queue up to the given count of writes in the handle while the buffer is full,
+  /// the oldest queued write is discarded with `HandleError::Dropped` if the queue is full.
+  DropOldest(usize),
+  /// Only keep the latest write while the buffer is full, replacing the previous one.
+  /// The same as `DropOldest(1)`.
+  CoalesceLatest,
+}
+
+/// Overflow state shared by clones of a handle.
+struct Overflow<T> {
+  policy: OverflowPolicy,
+  dropped: AtomicU64,
+  pending: Mutex<Pending<T>>,
+}
+
+/// Writes waiting for buffer space, used by `DropOldest` and `CoalesceLatest`.
+struct Pending<T> {
+  queue: VecDeque<WritePayload<T>>,
+  /// Whether a task is moving queued writes to the node.
+  pumping: bool,
+}
+
+impl<T> Overflow<T> {
+  fn new(policy: OverflowPolicy) -> Self {
+    Self {
+      policy,
+      dropped: AtomicU64::new(0),
+      pending: Mutex::new(Pending {
+        queue: VecDeque::new(),
+        pumping: false,
+      }),
+    }
+  }
+}
+
+impl<T: Send + 'static> Overflow<T> {
+  fn drop_payload(&self, payload: WritePayload<T>, e: HandleError) {
+    self.dropped.fetch_add(1, Ordering::Relaxed);
+    (payload.callback)(Err(Box::new(e)));
+  }
+
+  fn push(self: &Arc<Self>, tx: &WriteTx<T>, payload: WritePayload<T>) {
+    let capacity = match self.policy {
+      OverflowPolicy::DropOldest(n) => n.max(1),
+      _ => 1,
+    };
+
+    // callbacks are called after the lock is released
+    let mut closed = None;
+    let mut evicted = None;
+    {
+      let mut pending = self.pending.lock().unwrap();
+      // keep the order, send directly only if nothing is queued
+      let payload = if pending.queue.is_empty() && !pending.pumping {
+        match tx.try_send(payload) {
+          Ok(()) => None,
+          Err(TrySendError::Closed(payload)) => {
+            closed = Some(payload);
+            None
+          }
+          Err(TrySendError::Full(payload)) => Some(payload),
+        }
+      } else {
+        Some(payload)
+      };
+      if let Some(payload) = payload {
+        if pending.queue.len() >= capacity {
+          evicted = pending.queue.pop_front();
+        }
+        pending.queue.push_back(payload);
+        if !pending.pumping {
+          pending.pumping = true;
+          tokio::spawn(self.clone().pump(tx.clone()));
+        }
+      }
+    }
+    if let Some(payload) = closed {
+      (payload.callback)(Err(Box::new(HandleError::ChannelClosed)));
+    }
+    if let Some(payload) = evicted {
+      self.drop_payload(payload, HandleError::Dropped);
+    }
+  }
+
+  /// Move queued writes to the node as soon as there is buffer space.
+  async fn pump(self: Arc<Self>, tx: WriteTx<T>) {
+    loop {
+      let permit = tx.reserve().await;
+      let closed: Vec<_> = {
+        let mut pending = self.pending.lock().unwrap();
+        match permit {
+          Ok(permit) => {
+            if let Some(payload) = pending.queue.pop_front() {
+              permit.send(payload);
+              continue;
+            }
+            pending.pumping = false;
+            return;
+          }
+          Err(_) => {
+            pending.pumping = false;
+            pending.queue.drain(..).collect()
+          }
+        }
+      };
+      for payload in closed {
+        (payload.callback)(Err(Box::new(HandleError::ChannelClosed)));
+      }
+      return;
+    }
+  }
+}
+
+type ReserveFuture<T> =
+  Pin<Box<dyn Future<Output = Result<OwnedPermit<WritePayload<T>>, SendError<()>>> + Send>>;
+
+/// HandleSink writes items to the node of a `Handle`.
+///
+/// Flushing waits until all sent items are written or discarded by the node,
+/// use `Handle::write_then` to get the result of each write.
+pub struct HandleSink<T = Bytes> {
+  tx: WriteTx<T>,
+  reserve: Option<ReserveFuture<T>>,
+  permit: Option<OwnedPermit<WritePayload<T>>>,
+  in_flight: usize,
+  done_tx: UnboundedSender<()>,
+  done_rx: UnboundedReceiver<()>,
+}
+
+/// Notify the sink when a payload is dropped, no matter it's written or discarded.
+struct DoneGuard(UnboundedSender<()>);
+
+impl Drop for DoneGuard {
+  fn drop(&mut self) {
+    self.0.send(()).ok();
+  }
+}
+
+impl<T: Send + 'static> Sink<T> for HandleSink<T> {
+  type Error = HandleError;
+
+  fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), HandleError>> {
+    if self.permit.is_some() {
+      return Poll::Ready(Ok(()));
+    }
+    let tx = self.tx.clone();
+    let reserve = self
+      .reserve
+      .get_or_insert_with(|| Box::pin(tx.reserve_owned()));
+    match reserve.as_mut().poll(cx) {
+      Poll::Ready(result) => {
+        self.reserve = None;
+        match result {
+          Ok(permit) => {
+            self.permit = Some(permit);
+            Poll::Ready(Ok(()))
+          }
+          Err(_) => Poll::Ready(Err(HandleError::ChannelClosed)),
+        }
+      }
+      Poll::Pending => Poll::Pending,
+    }
+  }
+
+  fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<(), HandleError> {
+    // `poll_ready` must be called before
+    let permit = self.permit.take().ok_or(HandleError::ChannelClosed)?;
+    let guard = DoneGuard(self.done_tx.clone());
+    permit.send(WritePayload::with_data(item).callback(move |_| {
+      let _ = &guard;
+    }));
+    self.in_flight += 1;
+    Ok(())
+  }
+
+  fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), HandleError>> {
+    while self.in_flight > 0 {
+      match self.done_rx.poll_recv(cx) {
+        Poll::Ready(_) => self.in_flight -= 1,
+        Poll::Pending => return Poll::Pending,
+      }
+    }
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), HandleError>> {
+    self.poll_flush(cx)
+  }
+}
+
+/// Create a callback which sends its result to the receiver once.
+fn done_callback() -> (
+  impl Fn(GeneralResult<()>) + Send + Sync + 'static,
+  oneshot::Receiver<Result<(), HandleError>>,
+) {
+  let (done_tx, done_rx) = oneshot::channel();
+  let done_tx = Mutex::new(Some(done_tx));
+  let callback = move |result: GeneralResult<()>| {
+    if let Some(done_tx) = done_tx.lock().unwrap().take() {
+      done_tx.send(result.map_err(HandleError::from)).ok();
+    }
+  };
+  (callback, done_rx)
 }
 
 #[derive(Debug)]
 pub enum HandleError {
   ChannelClosed,
   Timeout,
+  BufferFull,
+  /// Discarded by the overflow policy of the handle.
+  Dropped,
+  /// The node failed to write, with the message of the error.
+  WriteFailed(String),
+}
+
+impl From<Box<dyn Error>> for HandleError {
+  fn from(e: Box<dyn Error>) -> Self {
+    match e.downcast::<HandleError>() {
+      Ok(e) => *e,
+      Err(e) => HandleError::WriteFailed(e.to_string()),
+    }
+  }
 }
 
 impl Display for HandleError {
@@ -245,8 +683,132 @@ impl Display for HandleError {
     match self {
       HandleError::ChannelClosed => write!(f, "channel closed"),
       HandleError::Timeout => write!(f, "timeout"),
+      HandleError::BufferFull => write!(f, "buffer full"),
+      HandleError::Dropped => write!(f, "dropped"),
+      HandleError::WriteFailed(e) => write!(f, "write failed: {}", e),
     }
   }
 }
 
 impl Error for HandleError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A handle of a node which never reads, with a buffer of one write.
+  fn stalled(policy: OverflowPolicy) -> (Handle<u32>, WriteRx<u32>) {
+    let (tx, rx) = mpsc::channel(1);
+    let (stop_tx, _) = mpsc::channel(1);
+    let mut handle = HandleBuilder::new()
+      .tx(tx)
+      .stop_tx(stop_tx)
+      .build()
+      .unwrap();
+    handle.set_overflow(policy);
+    (handle, rx)
+  }
+
+  /// Write `n` and record its failure, if any.
+  fn write(handle: &Handle<u32>, n: u32, failed: &Arc<Mutex<Vec<(u32, String)>>>) {
+    let failed = failed.clone();
+    handle.write_then(n, move |result| {
+      if let Err(e) = result {
+        failed.lock().unwrap().push((n, e.to_string()));
+      }
+    });
+  }
+
+  async fn recv_all(rx: &mut WriteRx<u32>, count: usize) -> Vec<u32> {
+    let mut received = Vec::new();
+    for _ in 0..count {
+      let payload = rx.recv().await.unwrap();
+      (payload.callback)(Ok(()));
+      received.push(payload.data);
+    }
+    assert!(rx.try_recv().is_err());
+    received
+  }
+
+  #[tokio::test]
+  async fn fail_fast() {
+    let (handle, mut rx) = stalled(OverflowPolicy::FailFast);
+    handle.write(1);
+    assert!(matches!(handle.send(2).await, Err(HandleError::BufferFull)));
+    assert_eq!(handle.dropped(), 1);
+    assert_eq!(recv_all(&mut rx, 1).await, [1]);
+  }
+
+  #[tokio::test]
+  async fn drop_newest() {
+    let (handle, mut rx) = stalled(OverflowPolicy::DropNewest);
+    handle.write(1);
+    assert!(matches!(handle.send(2).await, Err(HandleError::Dropped)));
+    assert!(matches!(handle.send(3).await, Err(HandleError::Dropped)));
+    assert_eq!(handle.dropped(), 2);
+    assert_eq!(recv_all(&mut rx, 1).await, [1]);
+  }
+
+  #[tokio::test]
+  async fn drop_oldest() {
+    let (handle, mut rx) = stalled(OverflowPolicy::DropOldest(2));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    for n in 1..=5 {
+      write(&handle, n, &failed);
+    }
+    // 1 is in the buffer, 2 and 3 are evicted by 4 and 5
+    let dropped = String::from("dropped");
+    assert_eq!(
+      *failed.lock().unwrap(),
+      [(2, dropped.clone()), (3, dropped)]
+    );
+    assert_eq!(handle.dropped(), 2);
+    assert_eq!(recv_all(&mut rx, 3).await, [1, 4, 5]);
+  }
+
+  #[tokio::test]
+  async fn coalesce_latest() {
+    let (handle, mut rx) = stalled(OverflowPolicy::CoalesceLatest);
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    for n in 1..=4 {
+      write(&handle, n, &failed);
+    }
+    let failed: Vec<_> = failed.lock().unwrap().iter().map(|(n, _)| *n).collect();
+    assert_eq!(failed, [2, 3]);
+    assert_eq!(handle.dropped(), 2);
+    assert_eq!(recv_all(&mut rx, 2).await, [1, 4]);
+  }
+
+  #[tokio::test]
+  async fn queued_writes_keep_order() {
+    let (handle, mut rx) = stalled(OverflowPolicy::DropOldest(8));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    for n in 1..=4 {
+      write(&handle, n, &failed);
+    }
+    // free one slot, later writes must stay behind the queued ones
+    assert_eq!(recv_all(&mut rx, 1).await, [1]);
+    for n in 5..=6 {
+      write(&handle, n, &failed);
+    }
+    assert_eq!(recv_all(&mut rx, 5).await, [2, 3, 4, 5, 6]);
+    assert!(failed.lock().unwrap().is_empty());
+    assert_eq!(handle.dropped(), 0);
+  }
+
+  #[tokio::test]
+  async fn queued_writes_fail_when_closed() {
+    let (handle, rx) = stalled(OverflowPolicy::DropOldest(3));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    for n in 1..=3 {
+      write(&handle, n, &failed);
+    }
+    drop(rx);
+    assert!(matches!(
+      handle.send(4).await,
+      Err(HandleError::ChannelClosed)
+    ));
+    let closed = String::from("channel closed");
+    assert_eq!(*failed.lock().unwrap(), [(2, closed.clone()), (3, closed)]);
+  }
+}
@@ -5,18 +5,47 @@ use tokio::sync::Mutex;
 
 use crate::{
   clone, go,
-  model::{GeneralResult, Handle},
+  model::{GeneralResult, Handle, HandleError},
 };
 
-#[derive(Clone, Default)]
-pub struct Broadcaster {
+/// Broadcaster writes a clone of the data to each target, `Bytes` by default.
+pub struct Broadcaster<T = Bytes> {
   timeout_ms: Option<u64>,
-  targets: Arc<Mutex<HashMap<usize, Handle>>>,
+  targets: Arc<Mutex<HashMap<usize, Handle<T>>>>,
   keep_dead_targets: bool,
   current_handle_id: Arc<Mutex<usize>>,
 }
 
-impl Broadcaster {
+impl<T> Clone for Broadcaster<T> {
+  fn clone(&self) -> Self {
+    Self {
+      timeout_ms: self.timeout_ms,
+      targets: self.targets.clone(),
+      keep_dead_targets: self.keep_dead_targets,
+      current_handle_id: self.current_handle_id.clone(),
+    }
+  }
+}
+
+impl Default for Broadcaster {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Clone + Send + 'static> Broadcaster<T> {
+  /// Use `default` for targets of `Bytes`.
+  pub fn new() -> Self {
+    Self {
+      timeout_ms: None,
+      targets: Arc::default(),
+      keep_dead_targets: false,
+      current_handle_id: Arc::default(),
+    }
+  }
+
+  /// A target is dead and removed if a write to it fails,
+  /// except writes discarded by its overflow policy. Default: false.
   pub fn keep_dead_targets(mut self, enable: bool) -> Self {
     self.keep_dead_targets = enable;
     self
@@ -27,11 +56,11 @@ impl Broadcaster {
     self
   }
 
-  pub fn add_target(&mut self, handle: Handle) {
+  pub fn add_target(&mut self, handle: Handle<T>) {
     self.add_target_then(handle, |_| {})
   }
 
-  pub fn add_target_then<F>(&mut self, handle: Handle, callback: F)
+  pub fn add_target_then<F>(&mut self, handle: Handle<T>, callback: F)
   where
     F: Fn(usize) + Send + Sync + 'static,
   {
@@ -57,19 +86,19 @@ impl Broadcaster {
 
   pub fn remove_target_then<F>(&self, id: usize, callback: F)
   where
-    F: Fn(Option<Handle>) + Send + Sync + 'static,
+    F: Fn(Option<Handle<T>>) + Send + Sync + 'static,
   {
     clone!(self, targets);
     go! { callback(targets.lock().await.remove(&id)) };
   }
 
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn write(&self, data: Bytes) {
+  pub fn write(&self, data: T) {
     self.inner_write(data, self.timeout_ms, |_| {})
   }
 
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn write_then<F>(&self, data: Bytes, callback: F)
+  pub fn write_then<F>(&self, data: T, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
@@ -78,20 +107,20 @@ impl Broadcaster {
 
   /// Override the default timeout.
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn timed_write(&self, data: Bytes, timeout_ms: u64) {
+  pub fn timed_write(&self, data: T, timeout_ms: u64) {
     self.inner_write(data, Some(timeout_ms), |_| {})
   }
 
   /// Override the default timeout.
   /// Write will be canceled if timeout, in this case you may need to increase the node's buffer.
-  pub fn timed_write_then<F>(&self, data: Bytes, timeout_ms: u64, callback: F)
+  pub fn timed_write_then<F>(&self, data: T, timeout_ms: u64, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
     self.inner_write(data, Some(timeout_ms), callback)
   }
 
-  fn inner_write<F>(&self, data: Bytes, timeout_ms: Option<u64>, callback: F)
+  fn inner_write<F>(&self, data: T, timeout_ms: Option<u64>, callback: F)
   where
     F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
   {
@@ -101,20 +130,20 @@ impl Broadcaster {
 
       for (id, handle) in targets_locked.iter() {
         let targets = targets.clone();
-        let keep_dead_targets = keep_dead_targets.clone();
+        let keep_dead_targets = keep_dead_targets;
         let id = *id;
         let callback = callback.clone();
 
         let callback = move |result: GeneralResult<()>| {
           let targets = targets.clone();
-          if result.is_err() && !keep_dead_targets {
+          if is_dead(&result) && !keep_dead_targets {
             go! { targets.lock().await.remove(&id) };
           }
           callback(result);
         };
 
         if let Some(timeout_ms) = timeout_ms {
-          handle.timed_write_then(data.clone(), timeout_ms.clone(), callback.clone());
+          handle.timed_write_then(data.clone(), timeout_ms, callback.clone());
         } else {
           handle.write_then(data.clone(), callback.clone());
         }
@@ -133,7 +162,7 @@ impl Broadcaster {
     clone!(self, targets);
     go! {
       let mut targets = targets.lock().await;
-      let keys: Vec<usize> = targets.keys().map(|x| *x).collect();
+      let keys: Vec<usize> = targets.keys().copied().collect();
       for id in keys {
         if let Some(handle) = targets.remove(&id) {
           handle.stop_then(callback.clone());
@@ -142,3 +171,14 @@ impl Broadcaster {
     };
   }
 }
+
+/// Writes discarded by the overflow policy of a slow target don't make the target dead.
+fn is_dead(result: &GeneralResult<()>) -> bool {
+  match result {
+    Ok(()) => false,
+    Err(e) => !matches!(
+      e.downcast_ref::<HandleError>(),
+      Some(HandleError::Dropped | HandleError::BufferFull)
+    ),
+  }
+}
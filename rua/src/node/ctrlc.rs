@@ -4,7 +4,8 @@ use tokio::sync::mpsc;
 
 use crate::{
   go,
-  model::{HandleBuilder, StopOnlyHandle, StopRx},
+  model::{GeneralResult, HandleBuilder, StopOnlyHandle, StopRx},
+  node::Node,
   take,
 };
 
@@ -74,3 +75,15 @@ impl Ctrlc {
     }
   }
 }
+
+impl Node for Ctrlc {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    Ctrlc::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    Ok(Ctrlc::spawn(self))
+  }
+}
@@ -0,0 +1,5 @@
+pub mod node;
+pub mod rotate;
+
+pub use node::{FileNode, SyncPolicy};
+pub use rotate::{RotateHandle, RotateInterval, RotateNaming, Rotation};
@@ -0,0 +1,315 @@
+use std::{
+  io,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use bytes::BytesMut;
+use tokio::{
+  fs::{File, OpenOptions},
+  io::AsyncWriteExt,
+  sync::mpsc::{self, Receiver},
+  time::{self, Instant},
+};
+
+use crate::{
+  codec::Codec,
+  go,
+  model::{
+    CallbackFn, CloseHandler, CloseReason, GeneralResult, Handle, HandleBuilder, StopRx, WriteRx,
+  },
+  node::{Node, Sink},
+  take, take_mut, take_option,
+};
+
+use super::{RotateHandle, Rotation};
+
+/// When `FileNode` calls `fsync` to make writes durable.
+///
+/// Write callbacks are called after the `fsync` covering the write is done,
+/// so a successful write is always durable, except with `SyncPolicy::Never`.
+/// Pending writes are synced before the node is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncPolicy {
+  /// Sync after every write.
+  #[default]
+  Always,
+  /// Sync after the given count of writes, or when no more writes are queued,
+  /// so writes arriving together share one sync and a write never waits for later writes.
+  EveryN(usize),
+  /// Sync at most the given milliseconds after the first unsynced write.
+  Interval(u64),
+  /// Never sync, the data is written to the OS which decides when to flush it to the disk.
+  Never,
+}
+
+pub struct FileNode<'a> {
+  handle: Handle,
+  filename: Option<&'a str>,
+  codec: Codec,
+  sync_policy: SyncPolicy,
+  rotation: Rotation,
+  rotate_handle: RotateHandle,
+  close_handler: Option<CloseHandler>,
+  rx: WriteRx,
+  stop_rx: StopRx,
+  rotate_rx: Receiver<CallbackFn>,
+}
+
+impl<'a> Default for FileNode<'a> {
+  fn default() -> Self {
+    Self::new(16)
+  }
+}
+
+impl<'a> FileNode<'a> {
+  pub fn new(buffer: usize) -> Self {
+    let (tx, rx) = mpsc::channel(buffer);
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+    let (rotate_tx, rotate_rx) = mpsc::channel(1);
+
+    Self {
+      handle: HandleBuilder::default()
+        .tx(tx)
+        .stop_tx(stop_tx)
+        .build()
+        .unwrap(),
+      filename: None,
+      codec: Codec::default(),
+      sync_policy: SyncPolicy::default(),
+      rotation: Rotation::default(),
+      rotate_handle: RotateHandle::new(rotate_tx),
+      close_handler: None,
+      stop_rx,
+      rx,
+      rotate_rx,
+    }
+  }
+
+  pub fn filename(mut self, filename: &'a str) -> Self {
+    self.filename = Some(filename);
+    self
+  }
+
+  /// Set the codec used to frame messages in the file. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.codec = codec;
+    self
+  }
+
+  /// Set when to sync writes to the disk. Default: `SyncPolicy::Always`.
+  pub fn sync_policy(mut self, policy: SyncPolicy) -> Self {
+    self.sync_policy = policy;
+    self
+  }
+
+  /// Rotate the file by size or time. Default: no rotation.
+  pub fn rotation(mut self, rotation: Rotation) -> Self {
+    self.rotation = rotation;
+    self
+  }
+
+  /// Called once when the node is closed, with the reason.
+  /// A failed write to the file closes the node with `CloseReason::WriteError`,
+  /// while a message which can't be encoded only fails its own write.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+
+  /// The rotate handle triggers a rotation, with the naming and retention of `rotation`.
+  pub fn rotate_handle(&self) -> &RotateHandle {
+    &self.rotate_handle
+  }
+
+  pub async fn spawn(self) -> GeneralResult<Handle> {
+    take_option!(self, filename);
+    let mut log = LogFile::open(PathBuf::from(filename), self.rotation).await?;
+
+    // writer thread
+    take_mut!(self, stop_rx, rx, rotate_rx);
+    take!(self, codec, sync_policy, close_handler);
+    go! {
+      let mut unsynced = Unsynced::new(sync_policy);
+      let mut rotate_deadline = log.rotation.next_deadline();
+      let (reason, payload) = loop {
+        tokio::select! {
+          Some(payload) = stop_rx.recv() => {
+            unsynced.sync(&mut log.file).await;
+            break (CloseReason::Stopped, Some(payload))
+          }
+          _ = time::sleep_until(unsynced.deadline.unwrap_or_else(Instant::now)),
+            if unsynced.deadline.is_some() => {
+            unsynced.sync(&mut log.file).await;
+          }
+          _ = time::sleep_until(rotate_deadline.unwrap_or_else(Instant::now)),
+            if rotate_deadline.is_some() => {
+            if log.size > 0 {
+              // retry at the next interval if failed
+              log.rotate(&mut unsynced).await.ok();
+            }
+            rotate_deadline = log.rotation.next_deadline();
+          }
+          Some(callback) = rotate_rx.recv() => {
+            match log.rotate(&mut unsynced).await {
+              Ok(()) => callback(Ok(())),
+              Err(e) => callback(Err(Box::new(e))),
+            }
+          }
+          payload = rx.recv() => {
+            if let Some(payload) = payload {
+              let mut buf = BytesMut::new();
+              if let Err(e) = codec.encode(&payload.data, &mut buf) {
+                (payload.callback)(Err(Box::new(e)));
+                continue
+              }
+              if log.rotation.exceeds(log.size, buf.len()) {
+                // keep writing to the current file if failed, and retry on the next write
+                log.rotate(&mut unsynced).await.ok();
+              }
+              if let Err(e) = log.write(&buf).await {
+                (payload.callback)(Err(Box::new(io::Error::new(e.kind(), e.to_string()))));
+                unsynced.sync(&mut log.file).await;
+                break (CloseReason::WriteError(e), None)
+              }
+              unsynced.push(payload.callback);
+              if unsynced.is_due(rx.is_empty()) {
+                unsynced.sync(&mut log.file).await;
+              }
+            } else {
+              unsynced.sync(&mut log.file).await;
+              break (CloseReason::Stopped, None) // all tx are dropped
+            }
+          }
+        }
+      };
+      if let Some(close_handler) = close_handler {
+        close_handler(reason);
+      }
+      if let Some(payload) = payload {
+        (payload.callback)(Ok(()));
+      }
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
+    };
+    Ok(self.handle)
+  }
+}
+
+impl<'a> Node for FileNode<'a> {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    FileNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    FileNode::spawn(self).await
+  }
+}
+
+impl<'a> Sink for FileNode<'a> {}
+
+/// The file being written, with its size for rotation.
+struct LogFile {
+  file: File,
+  path: PathBuf,
+  size: u64,
+  rotation: Rotation,
+}
+
+impl LogFile {
+  async fn open(path: PathBuf, rotation: Rotation) -> io::Result<Self> {
+    let file = Self::open_file(&path).await?;
+    let size = file.metadata().await?.len();
+    Ok(Self {
+      file,
+      path,
+      size,
+      rotation,
+    })
+  }
+
+  async fn open_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .await
+  }
+
+  async fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+    self.file.write_all(buf).await?;
+    self.file.flush().await?;
+    self.size += buf.len() as u64;
+    Ok(())
+  }
+
+  /// Sync pending writes, rename the file and create a new one.
+  /// The current file is still used if it can't be renamed.
+  async fn rotate(&mut self, unsynced: &mut Unsynced) -> io::Result<()> {
+    unsynced.sync(&mut self.file).await;
+    self.rotation.rename(&self.path).await?;
+    self.file = Self::open_file(&self.path).await?;
+    self.size = 0;
+    self.rotation.cleanup(&self.path).await
+  }
+}
+
+/// Callbacks of writes which are not synced yet.
+struct Unsynced {
+  policy: SyncPolicy,
+  callbacks: Vec<CallbackFn>,
+  /// When to sync with `SyncPolicy::Interval`.
+  deadline: Option<Instant>,
+}
+
+impl Unsynced {
+  fn new(policy: SyncPolicy) -> Self {
+    Self {
+      policy,
+      callbacks: Vec::new(),
+      deadline: None,
+    }
+  }
+
+  fn push(&mut self, callback: CallbackFn) {
+    if let SyncPolicy::Never = self.policy {
+      return callback(Ok(()));
+    }
+    if let (SyncPolicy::Interval(ms), None) = (self.policy, self.deadline) {
+      self.deadline = Some(Instant::now() + Duration::from_millis(ms));
+    }
+    self.callbacks.push(callback);
+  }
+
+  /// `idle` is whether no more writes are queued.
+  fn is_due(&self, idle: bool) -> bool {
+    match self.policy {
+      SyncPolicy::Always => true,
+      SyncPolicy::EveryN(n) => idle || self.callbacks.len() >= n,
+      SyncPolicy::Interval(_) | SyncPolicy::Never => false,
+    }
+  }
+
+  /// Sync the file and call all pending callbacks with the result.
+  async fn sync(&mut self, file: &mut File) {
+    self.deadline = None;
+    if self.callbacks.is_empty() {
+      return;
+    }
+    let result = file.sync_data().await;
+    for callback in self.callbacks.drain(..) {
+      match &result {
+        Ok(()) => callback(Ok(())),
+        Err(e) => callback(Err(Box::new(io::Error::new(e.kind(), e.to_string())))),
+      }
+    }
+  }
+}
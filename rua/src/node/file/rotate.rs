@@ -0,0 +1,405 @@
+use std::{
+  io,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{fs, sync::mpsc::Sender, time::Instant};
+
+use crate::{
+  clone, go,
+  model::{CallbackFn, GeneralResult, HandleError},
+};
+
+pub type RotateTx = Sender<CallbackFn>;
+
+/// Rotate at UTC hour or day boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotateInterval {
+  Hourly,
+  Daily,
+}
+
+impl RotateInterval {
+  fn secs(&self) -> u64 {
+    match self {
+      RotateInterval::Hourly => 3600,
+      RotateInterval::Daily => 86400,
+    }
+  }
+}
+
+/// How rotated files are named, e.g. for `log.txt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotateNaming {
+  /// `log.txt.1` is the newest rotated file, older files are renamed to `log.txt.2`, `log.txt.3`, etc.
+  #[default]
+  Numbered,
+  /// `log.txt.20211018-130000`, the UTC time of the rotation.
+  /// A counter is appended if the name is taken, e.g. `log.txt.20211018-130000.1`.
+  Timestamped,
+}
+
+/// Log rotation of `FileNode`.
+///
+/// The current file is renamed and a new file is created with the original name.
+/// Nothing is rotated by default, until `max_size` or `interval` is set,
+/// or the rotation is triggered by `RotateHandle::rotate`.
+#[derive(Debug, Clone, Default)]
+pub struct Rotation {
+  max_size: Option<u64>,
+  interval: Option<RotateInterval>,
+  naming: RotateNaming,
+  keep: Option<usize>,
+  max_age_secs: Option<u64>,
+}
+
+impl Rotation {
+  /// Rotate before a write which would make the file larger than `bytes`.
+  /// A single write larger than `bytes` is written to an empty file.
+  pub fn max_size(mut self, bytes: u64) -> Self {
+    self.max_size = Some(bytes);
+    self
+  }
+
+  /// Rotate at the end of each hour or day. Empty files are not rotated.
+  pub fn interval(mut self, interval: RotateInterval) -> Self {
+    self.interval = Some(interval);
+    self
+  }
+
+  /// Default: `RotateNaming::Numbered`.
+  pub fn naming(mut self, naming: RotateNaming) -> Self {
+    self.naming = naming;
+    self
+  }
+
+  /// Keep at most `count` rotated files, older files are deleted after each rotation.
+  pub fn keep(mut self, count: usize) -> Self {
+    self.keep = Some(count);
+    self
+  }
+
+  /// Delete rotated files last modified more than `secs` ago after each rotation.
+  pub fn max_age_secs(mut self, secs: u64) -> Self {
+    self.max_age_secs = Some(secs);
+    self
+  }
+
+  /// Whether writing `len` bytes to a file of `size` bytes needs a rotation first.
+  pub(crate) fn exceeds(&self, size: u64, len: usize) -> bool {
+    match self.max_size {
+      Some(max_size) => size > 0 && size + len as u64 > max_size,
+      None => false,
+    }
+  }
+
+  /// When the next time based rotation is due.
+  pub(crate) fn next_deadline(&self) -> Option<Instant> {
+    let interval = self.interval?.secs();
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+    let next = Duration::from_secs((now.as_secs() / interval + 1) * interval);
+    Some(Instant::now() + (next - now))
+  }
+
+  /// Rename the file at `path` to the next rotated name.
+  pub(crate) async fn rename(&self, path: &Path) -> io::Result<()> {
+    let rotated = match self.naming {
+      RotateNaming::Numbered => {
+        // shift older files first, from the oldest
+        for (file, n) in rotated_files(path, self.naming).await?.into_iter().rev() {
+          fs::rename(&file, numbered(path, n + 1)).await?;
+        }
+        numbered(path, 1)
+      }
+      RotateNaming::Timestamped => {
+        let timestamp = utc_timestamp(SystemTime::now());
+        let mut rotated = with_suffix(path, &timestamp);
+        // continue the counter of the newest file, which may be deleted by the retention
+        let base = parse_timestamped(&timestamp).unwrap_or_default();
+        let newest = rotated_files(path, self.naming)
+          .await?
+          .first()
+          .map_or(0, |(_, order)| u64::MAX - order);
+        let mut n = 0;
+        if newest >= base && newest - base < 1000 {
+          n = newest - base + 1;
+          rotated = with_suffix(path, &format!("{}.{}", timestamp, n));
+        }
+        while fs::metadata(&rotated).await.is_ok() {
+          n += 1;
+          rotated = with_suffix(path, &format!("{}.{}", timestamp, n));
+        }
+        rotated
+      }
+    };
+    fs::rename(path, rotated).await
+  }
+
+  /// Delete rotated files exceeding `keep` or `max_age_secs`.
+  /// A file which can't be inspected or deleted, e.g. removed by others meanwhile, is skipped.
+  pub(crate) async fn cleanup(&self, path: &Path) -> io::Result<()> {
+    if self.keep.is_none() && self.max_age_secs.is_none() {
+      return Ok(());
+    }
+    let max_age = self.max_age_secs.map(Duration::from_secs);
+    for (i, (file, _)) in rotated_files(path, self.naming).await?.iter().enumerate() {
+      let expired = match max_age {
+        Some(max_age) => fs::metadata(file)
+          .await
+          .and_then(|metadata| metadata.modified())
+          .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > max_age)),
+        None => false,
+      };
+      if expired || self.keep.is_some_and(|keep| i >= keep) {
+        fs::remove_file(file).await.ok();
+      }
+    }
+    Ok(())
+  }
+}
+
+/// RotateHandle triggers a rotation of a `FileNode`, e.g. on `SIGHUP`.
+#[derive(Clone)]
+pub struct RotateHandle {
+  tx: RotateTx,
+}
+
+impl RotateHandle {
+  pub(crate) fn new(tx: RotateTx) -> Self {
+    Self { tx }
+  }
+
+  /// Rotate the file even if it's empty.
+  pub fn rotate(&self) {
+    self.rotate_then(|_| {})
+  }
+
+  pub fn rotate_then<F>(&self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    clone!(self, tx);
+    go! {
+      if tx.send(Box::new(callback.clone())).await.is_err() {
+        callback(Err(Box::new(HandleError::ChannelClosed)));
+      }
+    };
+  }
+}
+
+/// Rotated files of `path` with their order, newest first.
+async fn rotated_files(path: &Path, naming: RotateNaming) -> io::Result<Vec<(PathBuf, u64)>> {
+  let dir = match path.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+  let prefix = match path.file_name().and_then(|name| name.to_str()) {
+    Some(name) => format!("{}.", name),
+    None => return Ok(Vec::new()),
+  };
+
+  let mut files = Vec::new();
+  let mut entries = fs::read_dir(dir).await?;
+  while let Some(entry) = entries.next_entry().await? {
+    let name = entry.file_name();
+    let suffix = match name.to_str().and_then(|name| name.strip_prefix(&prefix)) {
+      Some(suffix) => suffix,
+      None => continue,
+    };
+    let order = match naming {
+      RotateNaming::Numbered => suffix.parse().ok().filter(|n| *n > 0),
+      RotateNaming::Timestamped => parse_timestamped(suffix).map(|order| u64::MAX - order),
+    };
+    if let Some(order) = order {
+      files.push((entry.path(), order));
+    }
+  }
+  files.sort_by_key(|(_, order)| *order);
+  Ok(files)
+}
+
+/// Parse `20211018-130000` or `20211018-130000.1` into a sortable number.
+fn parse_timestamped(suffix: &str) -> Option<u64> {
+  let (timestamp, n) = match suffix.split_once('.') {
+    Some((timestamp, n)) => (timestamp, n.parse::<u64>().ok()?),
+    None => (suffix, 0),
+  };
+  let (date, time) = timestamp.split_once('-')?;
+  if date.len() != 8 || time.len() != 6 || n >= 1000 {
+    return None;
+  }
+  let date: u64 = date.parse().ok()?;
+  let time: u64 = time.parse().ok()?;
+  Some((date * 1_000_000 + time) * 1000 + n)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = path.as_os_str().to_os_string();
+  name.push(".");
+  name.push(suffix);
+  PathBuf::from(name)
+}
+
+fn numbered(path: &Path, n: u64) -> PathBuf {
+  with_suffix(path, &n.to_string())
+}
+
+/// Format as `20211018-130000` in UTC.
+fn utc_timestamp(time: SystemTime) -> String {
+  let secs = time
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+  let (year, month, day) = civil_from_days((secs / 86400) as i64);
+  let secs = secs % 86400;
+  format!(
+    "{:04}{:02}{:02}-{:02}{:02}{:02}",
+    year,
+    month,
+    day,
+    secs / 3600,
+    secs % 3600 / 60,
+    secs % 60
+  )
+}
+
+/// Convert days since 1970-01-01 to a date, see http://howardhinnant.github.io/date_algorithms.html.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An empty directory under the system temp directory.
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rua-rotate-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn touch(dir: &Path, names: &[&str]) {
+    for name in names {
+      std::fs::write(dir.join(name), b"").unwrap();
+    }
+  }
+
+  fn names(files: &[(PathBuf, u64)]) -> Vec<String> {
+    files
+      .iter()
+      .map(|(file, _)| file.file_name().unwrap().to_string_lossy().into_owned())
+      .collect()
+  }
+
+  #[test]
+  fn parse_timestamped_suffix() {
+    assert_eq!(
+      parse_timestamped("20211018-130000"),
+      Some(20211018130000000)
+    );
+    assert_eq!(
+      parse_timestamped("20211018-130000.12"),
+      Some(20211018130000012)
+    );
+    assert!(parse_timestamped("20211018-130000.1") > parse_timestamped("20211018-130000"));
+    assert!(parse_timestamped("20211018-130001") > parse_timestamped("20211018-130000.999"));
+
+    for suffix in [
+      "",
+      "1",
+      "20211018",
+      "2021101-130000",
+      "20211018-13000",
+      "20211018_130000",
+      "2021101a-130000",
+      "20211018-130000.",
+      "20211018-130000.x",
+      "20211018-130000.1000",
+    ] {
+      assert_eq!(parse_timestamped(suffix), None, "{}", suffix);
+    }
+  }
+
+  #[test]
+  fn civil_date() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+    assert_eq!(civil_from_days(18_918), (2021, 10, 18));
+
+    let time = UNIX_EPOCH + Duration::from_secs(18_918 * 86400 + 13 * 3600 + 4 * 60 + 5);
+    assert_eq!(utc_timestamp(time), "20211018-130405");
+  }
+
+  #[tokio::test]
+  async fn rotated_numbered_files() {
+    let dir = temp_dir("numbered");
+    touch(
+      &dir,
+      &[
+        "log.txt",
+        "log.txt.2",
+        "log.txt.10",
+        "log.txt.1",
+        "log.txt.0",
+        "log.txt.x",
+        "other.txt.3",
+      ],
+    );
+    let path = dir.join("log.txt");
+
+    let files = rotated_files(&path, RotateNaming::Numbered).await.unwrap();
+    assert_eq!(names(&files), ["log.txt.1", "log.txt.2", "log.txt.10"]);
+
+    Rotation::default().keep(2).cleanup(&path).await.unwrap();
+    let files = rotated_files(&path, RotateNaming::Numbered).await.unwrap();
+    assert_eq!(names(&files), ["log.txt.1", "log.txt.2"]);
+
+    std::fs::remove_dir_all(&dir).ok();
+  }
+
+  #[tokio::test]
+  async fn rotated_timestamped_files() {
+    let dir = temp_dir("timestamped");
+    touch(
+      &dir,
+      &[
+        "log.txt",
+        "log.txt.20211017-235959",
+        "log.txt.20211018-130000",
+        "log.txt.20211018-130000.1",
+        "log.txt.1",
+      ],
+    );
+    let path = dir.join("log.txt");
+
+    let files = rotated_files(&path, RotateNaming::Timestamped)
+      .await
+      .unwrap();
+    assert_eq!(
+      names(&files),
+      [
+        "log.txt.20211018-130000.1",
+        "log.txt.20211018-130000",
+        "log.txt.20211017-235959"
+      ]
+    );
+
+    std::fs::remove_dir_all(&dir).ok();
+  }
+}
@@ -3,15 +3,29 @@ pub mod ctrlc;
 pub mod file;
 pub mod state;
 pub mod stdio;
+pub mod stream;
 pub mod tail;
 pub mod tcp;
 pub mod time;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod traits;
+pub mod udp;
+#[cfg(unix)]
+pub mod unix;
 
 pub use broadcast::Broadcaster;
 pub use ctrlc::Ctrlc;
 pub use file::FileNode;
 pub use state::StateNode;
 pub use stdio::StdioNode;
+pub use stream::StreamNode;
 pub use tail::TailNode;
-pub use tcp::{TcpListener, TcpNode};
+pub use tcp::{ReconnectingTcpNode, TcpListener, TcpNode};
 pub use time::Ticker;
+#[cfg(feature = "tls")]
+pub use tls::{TlsListener, TlsNode};
+pub use traits::{Node, Sink, Source};
+pub use udp::UdpNode;
+#[cfg(unix)]
+pub use unix::{UnixListener, UnixNode};
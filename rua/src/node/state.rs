@@ -1,20 +1,36 @@
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
-use crate::{go, take_mut};
+use crate::{
+  go,
+  model::{GeneralResult, HandleBuilder, Stop, StopOnlyHandle, StopRx},
+  node::Node,
+  take_mut,
+};
+
+type StateFn<T> = Box<dyn FnOnce(&mut T) + Send>;
 
 pub struct StateNode<T: Send> {
   state: T,
-  f_rx: Receiver<Box<dyn FnOnce(&mut T) + Send>>,
+  f_rx: Receiver<StateFn<T>>,
+  stop_rx: StopRx,
   handle: StateNodeHandle<T>,
 }
 
 impl<T: Send + 'static> StateNode<T> {
   pub fn new(state: T, buffer: usize) -> Self {
     let (f_tx, f_rx) = mpsc::channel(buffer);
+    let (stop_tx, stop_rx) = mpsc::channel(1);
     Self {
       state,
       f_rx,
-      handle: StateNodeHandle { f_tx },
+      stop_rx,
+      handle: StateNodeHandle {
+        f_tx,
+        stop_only: HandleBuilder::default()
+          .stop_tx(stop_tx)
+          .build_stop_only()
+          .unwrap(),
+      },
     }
   }
 
@@ -27,19 +43,54 @@ impl<T: Send + 'static> StateNode<T> {
   }
 
   pub fn spawn(self) -> StateNodeHandle<T> {
-    take_mut!(self, state, f_rx);
+    take_mut!(self, state, f_rx, stop_rx);
     go! {
-      while let Some(f) = f_rx.recv().await {
-        f(&mut state);
+      loop {
+        tokio::select! {
+          Some(payload) = stop_rx.recv() => {
+            (payload.callback)(Ok(()));
+            break
+          }
+          f = f_rx.recv() => {
+            if let Some(f) = f {
+              f(&mut state);
+            } else {
+              break // all handles are dropped
+            }
+          }
+        }
       }
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
     };
     self.handle
   }
 }
 
-#[derive(Clone)]
+impl<T: Send + 'static> Node for StateNode<T> {
+  type Handle = StateNodeHandle<T>;
+
+  fn handle(&self) -> &StateNodeHandle<T> {
+    StateNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StateNodeHandle<T>> {
+    Ok(StateNode::spawn(self))
+  }
+}
+
 pub struct StateNodeHandle<T> {
-  f_tx: Sender<Box<dyn FnOnce(&mut T) + Send>>,
+  f_tx: Sender<StateFn<T>>,
+  stop_only: StopOnlyHandle,
+}
+
+// `derive(Clone)` would require `T: Clone`
+impl<T> Clone for StateNodeHandle<T> {
+  fn clone(&self) -> Self {
+    Self {
+      f_tx: self.f_tx.clone(),
+      stop_only: self.stop_only.clone(),
+    }
+  }
 }
 
 impl<T: 'static> StateNodeHandle<T> {
@@ -50,4 +101,29 @@ impl<T: 'static> StateNodeHandle<T> {
     let f_tx = self.f_tx.clone();
     tokio::spawn(async move { f_tx.send(Box::new(f)).await });
   }
+
+  /// Functions which are not applied yet are discarded.
+  pub fn stop(self) {
+    self.stop_only.stop()
+  }
+
+  pub fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.stop_only.stop_then(callback)
+  }
+}
+
+impl<T: 'static> Stop for StateNodeHandle<T> {
+  fn stop(self) {
+    StateNodeHandle::stop(self)
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    StateNodeHandle::stop_then(self, callback)
+  }
 }
@@ -1,20 +1,30 @@
+use std::future::Future;
+
 use bytes::Bytes;
 use tokio::{
-  io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+  io::{self, AsyncWriteExt, BufReader},
   sync::mpsc,
 };
 
 use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
   go,
-  model::{Handle, HandleBuilder, StopRx, WriteRx},
-  take_mut,
+  model::{
+    CloseHandler, CloseReason, GeneralResult, Handle, HandleBuilder, Handler, StopRx, WriteRx,
+  },
+  node::{Node, Sink, Source},
+  take, take_mut,
 };
 
 /// StdioNode is useful to print messages to stdout.
 /// If you use `on_input` to register an stdin message handler, you may need to press Enter after you press Ctrl-C.
 pub struct StdioNode {
-  input_handler: Option<Box<dyn FnMut(Bytes) + Send>>,
+  input_handler: Option<Handler<Bytes>>,
+  close_handler: Option<CloseHandler>,
+  close_on_eof: bool,
   handle: Handle,
+  codec: Codec,
+  max_frame_len: usize,
   rx: WriteRx,
   stop_rx: StopRx,
 }
@@ -32,21 +42,64 @@ impl StdioNode {
 
     Self {
       input_handler: None,
+      close_handler: None,
+      close_on_eof: false,
       handle: HandleBuilder::default()
         .tx(tx)
         .stop_tx(stop_tx)
         .build()
         .unwrap(),
+      codec: Codec::default(),
+      max_frame_len: DEFAULT_MAX_FRAME_LEN,
       rx,
       stop_rx,
     }
   }
 
+  /// Set the codec used for both stdin and stdout. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.codec = codec;
+    self
+  }
+
+  /// Max length of a frame read from stdin. Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn max_frame_len(mut self, len: usize) -> Self {
+    self.max_frame_len = len;
+    self
+  }
+
   pub fn on_input<F>(mut self, f: F) -> Self
   where
     F: FnMut(Bytes) + Send + 'static,
   {
-    self.input_handler = Some(Box::new(f));
+    self.input_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before reading the next frame.
+  pub fn on_input_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  /// Close the node when stdin reaches the end or fails to be read.
+  /// Default: `false`, reading stops and stdout is still writable.
+  pub fn close_on_eof(mut self, close: bool) -> Self {
+    self.close_on_eof = close;
+    self
+  }
+
+  /// Called once when the node is closed, with the reason.
+  /// The end of stdin is reported only if `close_on_eof` is set.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
     self
   }
 
@@ -56,45 +109,55 @@ impl StdioNode {
 
   pub fn spawn(self) -> Handle {
     take_mut!(self, stop_rx, rx);
+    take!(self, codec, max_frame_len, close_handler, close_on_eof);
     let (reader_stop_tx, mut reader_stop_rx) = mpsc::channel(1);
     let (writer_stop_tx, mut writer_stop_rx) = mpsc::channel(1);
+    let (reason_tx, mut reason_rx) = mpsc::channel(1);
 
     // stopper thread
     go! {
-      if let Some(payload) = stop_rx.recv().await {
-        reader_stop_tx.send(()).await.ok();
-        writer_stop_tx.send(()).await.ok();
+      let (reason, payload) = tokio::select! {
+        Some(payload) = stop_rx.recv() => (CloseReason::Stopped, Some(payload)),
+        Some(reason) = reason_rx.recv() => (reason, None),
+        // all stop_tx are dropped and the reader is done
+        else => return,
+      };
+      reader_stop_tx.send(()).await.ok();
+      writer_stop_tx.send(()).await.ok();
+      if let Some(close_handler) = close_handler {
+        close_handler(reason);
+      }
+      if let Some(payload) = payload {
         (payload.callback)(Ok(()));
       }
-      // else, all stop_tx are dropped, stop_rx is disabled
 
       // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
     };
 
     // reader thread
     if let Some(mut input_handler) = self.input_handler {
+      let reason_tx = close_on_eof.then(|| reason_tx.clone());
       go! {
-        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdin = BufReader::new(tokio::io::stdin());
 
-        loop {
+        let reason = loop {
           tokio::select! {
             Some(()) = reader_stop_rx.recv() => {
-              break
+              return
             }
-            // `next_line` will discard `\n` or `\r`
-            r = lines.next_line() => {
+            // `Codec::Line` will discard the delimiter
+            r = codec.read_frame(&mut stdin, max_frame_len) => {
               match r {
-                Ok(option) => {
-                  if let Some(s) = option {
-                    (input_handler)(Bytes::from(s));
-                  } else {
-                    break
-                  }
-                }
-                Err(_) => break, // read error
+                Ok(Some(data)) => input_handler.call(data).await,
+                Ok(None) => break CloseReason::Eof,
+                Err(e) => break CloseReason::ReadError(e),
               }
             }
           }
+        };
+        // notify stopper thread
+        if let Some(reason_tx) = reason_tx {
+          reason_tx.send(reason).await.ok();
         }
       };
     }
@@ -110,14 +173,15 @@ impl StdioNode {
           payload = rx.recv() => {
             if let Some(payload) = payload {
               let result = async {
-                stdout.write_all(&payload.data).await?;
-                stdout.write_all(b"\n").await?;
+                codec.write_frame(&mut stdout, &payload.data).await?;
                 stdout.flush().await?;
                 io::Result::Ok(())
               }
               .await;
               if let Err(e) = result {
-                (payload.callback)(Err(Box::new(e)));
+                (payload.callback)(Err(Box::new(io::Error::new(e.kind(), e.to_string()))));
+                // notify stopper thread
+                reason_tx.send(CloseReason::WriteError(e)).await.ok();
                 break
               } else {
                 (payload.callback)(Ok(()));
@@ -133,3 +197,36 @@ impl StdioNode {
     self.handle
   }
 }
+
+impl Node for StdioNode {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    StdioNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(StdioNode::spawn(self))
+  }
+}
+
+impl Source for StdioNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_input(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(f)
+  }
+}
+
+impl Sink for StdioNode {}
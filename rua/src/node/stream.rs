@@ -0,0 +1,243 @@
+use std::future::Future;
+
+use bytes::Bytes;
+use tokio::{
+  io::{self, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+  sync::mpsc,
+};
+
+use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
+  go,
+  model::{
+    CloseHandler, CloseReason, GeneralResult, Handle, HandleBuilder, Handler, StopRx, WriteRx,
+  },
+  node::{Node, Sink, Source},
+  take, take_mut,
+};
+
+type BoxedReader = Box<dyn AsyncRead + Unpin + Send>;
+type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// StreamNode reads and writes framed messages over any byte stream,
+/// e.g. TCP, TLS, unix sockets, `tokio::io::duplex` pipes or child process pipes.
+pub struct StreamNode {
+  handle: Handle,
+  reader: BoxedReader,
+  writer: BoxedWriter,
+  codec: Codec,
+  max_frame_len: usize,
+  input_handler: Option<Handler<Bytes>>,
+  close_handler: Option<CloseHandler>,
+  rx: WriteRx,
+  stop_rx: StopRx,
+}
+
+impl StreamNode {
+  /// Split the stream into a read half and a write half.
+  pub fn new<S>(stream: S, buffer: usize) -> Self
+  where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+  {
+    let (reader, writer) = io::split(stream);
+    Self::from_halves(reader, writer, buffer)
+  }
+
+  /// Use separate reader and writer, e.g. `ChildStdout` and `ChildStdin` of a child process.
+  pub fn from_halves<R, W>(reader: R, writer: W, buffer: usize) -> Self
+  where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+  {
+    let (tx, rx) = mpsc::channel(buffer);
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      reader: Box::new(reader),
+      writer: Box::new(writer),
+      rx,
+      stop_rx,
+      codec: Codec::default(),
+      max_frame_len: DEFAULT_MAX_FRAME_LEN,
+      input_handler: None,
+      close_handler: None,
+      handle: HandleBuilder::default()
+        .tx(tx)
+        .stop_tx(stop_tx)
+        .build()
+        .unwrap(),
+    }
+  }
+
+  /// Set the codec used to frame messages. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.codec = codec;
+    self
+  }
+
+  /// Max length of a read frame, longer frames close the node with `InvalidData`.
+  /// Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn max_frame_len(mut self, len: usize) -> Self {
+    self.max_frame_len = len;
+    self
+  }
+
+  pub fn on_input<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before reading the next frame.
+  pub fn on_input_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  /// Called once when the node is closed, with the reason.
+  /// The input is read to detect the end of the stream even without `on_input`, and is discarded.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+
+  pub fn spawn(self) -> Handle {
+    take_mut!(self, stop_rx, rx, writer);
+    take!(self, codec, max_frame_len, reader, close_handler);
+
+    let (reader_stop_tx, mut reader_stop_rx) = mpsc::channel(1);
+    let (writer_stop_tx, mut writer_stop_rx) = mpsc::channel(1);
+    let (reason_tx, mut reason_rx) = mpsc::channel(1);
+
+    // reader thread
+    if self.input_handler.is_some() || close_handler.is_some() {
+      let mut input_handler = self.input_handler;
+      let reason_tx = reason_tx.clone();
+      go! {
+        let mut reader = BufReader::new(reader);
+
+        let reason = loop {
+          tokio::select! {
+            Some(()) = reader_stop_rx.recv() => {
+              return
+            }
+            r = codec.read_frame(&mut reader, max_frame_len) => {
+              match r {
+                Ok(Some(data)) => {
+                  if let Some(input_handler) = input_handler.as_mut() {
+                    input_handler.call(data).await;
+                  }
+                }
+                Ok(None) => break CloseReason::Eof,
+                Err(e) => break CloseReason::ReadError(e),
+              }
+            }
+          }
+        };
+        // notify stopper thread
+        reason_tx.send(reason).await.ok();
+      };
+    }
+
+    // stopper thread
+    go! {
+      let (reason, payload) = tokio::select! {
+        Some(payload) = stop_rx.recv() => (CloseReason::Stopped, Some(payload)),
+        Some(reason) = reason_rx.recv() => (reason, None),
+        // all stop_tx are dropped and the reader is done
+        else => return,
+      };
+      reader_stop_tx.send(()).await.ok();
+      writer_stop_tx.send(()).await.ok();
+      if let Some(close_handler) = close_handler {
+        close_handler(reason);
+      }
+      if let Some(payload) = payload {
+        (payload.callback)(Ok(()));
+      }
+
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
+    };
+
+    // writer thread
+    go! {
+      loop {
+        tokio::select! {
+          Some(()) = writer_stop_rx.recv() => {
+            break
+          }
+          payload = rx.recv() => {
+            if let Some(payload) = payload {
+              let result = async {
+                codec.write_frame(&mut writer, &payload.data).await?;
+                writer.flush().await?;
+                io::Result::Ok(())
+              }
+              .await;
+              if let Err(e) = result {
+                (payload.callback)(Err(Box::new(io::Error::new(e.kind(), e.to_string()))));
+                // notify stopper thread
+                reason_tx.send(CloseReason::WriteError(e)).await.ok();
+                break
+              } else {
+                (payload.callback)(Ok(()));
+              }
+            } else {
+              break // all tx are dropped
+            }
+          }
+        }
+      }
+      // e.g. send close_notify of TLS or FIN of TCP
+      writer.shutdown().await.ok();
+    };
+
+    self.handle
+  }
+}
+
+impl Node for StreamNode {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    StreamNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(StreamNode::spawn(self))
+  }
+}
+
+impl Source for StreamNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_input(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(f)
+  }
+}
+
+impl Sink for StreamNode {}
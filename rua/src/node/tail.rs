@@ -1,4 +1,5 @@
 use std::{
+  future::Future,
   io::{ErrorKind, SeekFrom},
   time::Duration,
 };
@@ -12,7 +13,10 @@ use tokio::{
 
 use crate::{
   go,
-  model::{GeneralResult, HandleBuilder, StopOnlyHandle, StopRx},
+  model::{
+    CloseHandler, CloseReason, GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx,
+  },
+  node::{Node, Source},
   take, take_mut, take_option_mut,
 };
 
@@ -20,7 +24,8 @@ pub struct TailNode<'a> {
   handle: StopOnlyHandle,
   filename: &'a str,
   stop_rx: StopRx,
-  line_handler: Option<Box<dyn FnMut(Bytes) + Send>>,
+  line_handler: Option<Handler<Bytes>>,
+  close_handler: Option<CloseHandler>,
   check_interval_ms: u64,
 }
 
@@ -35,6 +40,7 @@ impl<'a> TailNode<'a> {
         .unwrap(),
       filename,
       line_handler: None,
+      close_handler: None,
       stop_rx,
       check_interval_ms: 10,
     }
@@ -44,7 +50,17 @@ impl<'a> TailNode<'a> {
   where
     F: FnMut(Bytes) + Send + 'static,
   {
-    self.line_handler = Some(Box::new(f));
+    self.line_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before reading the next line.
+  pub fn on_new_line_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.line_handler = Some(Handler::new_async(f));
     self
   }
 
@@ -53,6 +69,17 @@ impl<'a> TailNode<'a> {
     self
   }
 
+  /// Called once when the node is closed, with the reason.
+  /// The end of the file is not a close, the node waits for new lines,
+  /// so the reason is `CloseReason::ReadError` or `CloseReason::Stopped`.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
   pub fn handle(&self) -> &StopOnlyHandle {
     &self.handle
   }
@@ -70,21 +97,20 @@ impl<'a> TailNode<'a> {
     let mut lines = BufReader::new(file).lines();
 
     take_mut!(self, stop_rx);
-    take!(self, check_interval_ms);
+    take!(self, check_interval_ms, close_handler);
 
     // reader thread
     go! {
-      loop {
+      let (reason, payload) = loop {
         tokio::select! {
           Some(payload) = stop_rx.recv() => {
-            (payload.callback)(Ok(()));
-            break
+            break (CloseReason::Stopped, Some(payload))
           }
           r = lines.next_line() => {
             match r {
               Ok(option) => {
                 if let Some(s) = option {
-                  (line_handler)(Bytes::from(s));
+                  line_handler.call(Bytes::from(s)).await;
                 } else {
                   // got file end, sleep for a while
                   time::sleep(Duration::from_millis(check_interval_ms)).await;
@@ -95,14 +121,52 @@ impl<'a> TailNode<'a> {
                   // got file end, sleep for a while
                   time::sleep(Duration::from_millis(check_interval_ms)).await;
                 } else {
-                  break // other error
+                  break (CloseReason::ReadError(err), None)
                 }
               }
             }
           }
         }
+      };
+      if let Some(close_handler) = close_handler {
+        close_handler(reason);
+      }
+      if let Some(payload) = payload {
+        (payload.callback)(Ok(()));
       }
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
     };
     Ok(self.handle)
   }
 }
+
+impl<'a> Node for TailNode<'a> {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    TailNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    TailNode::spawn(self).await
+  }
+}
+
+impl<'a> Source for TailNode<'a> {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_new_line(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_new_line_async(f)
+  }
+}
@@ -0,0 +1,142 @@
+use std::future::Future;
+
+use tokio::{net, sync::mpsc};
+
+use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
+  go,
+  model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx},
+  node::{Node, Source},
+  take, take_mut, take_option_mut,
+};
+
+use super::TcpNode;
+
+pub struct TcpListener<'a> {
+  addr: &'a str,
+  peer_handler: Option<Handler<TcpNode>>,
+  peer_write_buffer: usize,
+  peer_codec: Codec,
+  peer_max_frame_len: usize,
+  handle: StopOnlyHandle,
+  stop_rx: StopRx,
+}
+
+impl<'a> TcpListener<'a> {
+  pub fn bind(addr: &'a str) -> Self {
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      addr,
+      stop_rx,
+      peer_handler: None,
+      peer_write_buffer: 16,
+      peer_codec: Codec::default(),
+      peer_max_frame_len: DEFAULT_MAX_FRAME_LEN,
+      handle: HandleBuilder::default()
+        .stop_tx(stop_tx)
+        .build_stop_only()
+        .unwrap(),
+    }
+  }
+
+  pub fn peer_write_buffer(mut self, buffer: usize) -> Self {
+    self.peer_write_buffer = buffer;
+    self
+  }
+
+  /// Set the codec of new peers. Default: `Codec::lines()`.
+  pub fn peer_codec(mut self, codec: Codec) -> Self {
+    self.peer_codec = codec;
+    self
+  }
+
+  /// Set the max frame length of new peers. Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn peer_max_frame_len(mut self, len: usize) -> Self {
+    self.peer_max_frame_len = len;
+    self
+  }
+
+  pub fn on_new_peer(mut self, f: impl FnMut(TcpNode) + 'static + Send) -> Self {
+    self.peer_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before handling the next peer.
+  pub fn on_new_peer_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(TcpNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.peer_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  pub fn handle(&self) -> &StopOnlyHandle {
+    &self.handle
+  }
+
+  /// Return `Err` if missing `peer_handler` or failed bind to address.
+  pub async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    take_option_mut!(self, peer_handler);
+
+    let listener = net::TcpListener::bind(self.addr).await?;
+
+    take!(self, peer_write_buffer, peer_codec, peer_max_frame_len);
+    take_mut!(self, stop_rx);
+
+    go! {
+      loop {
+        tokio::select! {
+          result = listener.accept() => {
+            if let Ok((socket, addr)) = result {
+              let node = TcpNode::new(socket, addr, peer_write_buffer)
+                .codec(peer_codec)
+                .max_frame_len(peer_max_frame_len);
+              peer_handler.call(node).await;
+            } else {
+              break
+            }
+          }
+          Some(payload) = stop_rx.recv() => {
+            (payload.callback)(Ok(()));
+            break
+          }
+        }
+      }
+    };
+
+    Ok(self.handle)
+  }
+}
+
+impl<'a> Node for TcpListener<'a> {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    TcpListener::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    TcpListener::spawn(self).await
+  }
+}
+
+impl<'a> Source for TcpListener<'a> {
+  type Output = TcpNode;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(TcpNode) + Send + 'static,
+  {
+    self.on_new_peer(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(TcpNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_new_peer_async(f)
+  }
+}
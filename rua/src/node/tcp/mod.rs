@@ -0,0 +1,7 @@
+pub mod listener;
+pub mod node;
+pub mod reconnect;
+
+pub use listener::TcpListener;
+pub use node::TcpNode;
+pub use reconnect::ReconnectingTcpNode;
@@ -0,0 +1,134 @@
+use std::{future::Future, net::SocketAddr, time::Duration};
+
+use bytes::Bytes;
+use tokio::{net::TcpStream, time};
+
+use crate::{
+  codec::Codec,
+  model::{CloseReason, GeneralResult, Handle, HandleError},
+  node::{stream::StreamNode, Node, Sink, Source},
+};
+
+pub struct TcpNode {
+  node: StreamNode,
+  addr: SocketAddr,
+}
+
+impl TcpNode {
+  pub fn new(socket: TcpStream, addr: SocketAddr, buffer: usize) -> Self {
+    let (reader, writer) = socket.into_split();
+    Self {
+      node: StreamNode::from_halves(reader, writer, buffer),
+      addr,
+    }
+  }
+
+  /// Connect to a remote address as a client.
+  ///
+  /// Return `Err` if failed to connect.
+  pub async fn connect(addr: &str, buffer: usize) -> GeneralResult<Self> {
+    let socket = TcpStream::connect(addr).await?;
+    let addr = socket.peer_addr()?;
+    Ok(Self::new(socket, addr, buffer))
+  }
+
+  /// Connect to a remote address as a client.
+  ///
+  /// Return `Err` if failed to connect or timeout.
+  pub async fn timed_connect(addr: &str, buffer: usize, timeout_ms: u64) -> GeneralResult<Self> {
+    match time::timeout(
+      Duration::from_millis(timeout_ms),
+      Self::connect(addr, buffer),
+    )
+    .await
+    {
+      Ok(result) => result,
+      Err(_) => Err(Box::new(HandleError::Timeout)),
+    }
+  }
+
+  /// Set the codec used to frame messages. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.node = self.node.codec(codec);
+    self
+  }
+
+  /// Max length of a read frame, longer frames close the node with `InvalidData`.
+  /// Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn max_frame_len(mut self, len: usize) -> Self {
+    self.node = self.node.max_frame_len(len);
+    self
+  }
+
+  pub fn on_input<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.node = self.node.on_input(f);
+    self
+  }
+
+  /// The handler is awaited before reading the next frame.
+  pub fn on_input_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.node = self.node.on_input_async(f);
+    self
+  }
+
+  /// Called once when the node is closed, see `StreamNode::on_close`.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.node = self.node.on_close(f);
+    self
+  }
+
+  pub fn handle(&self) -> &Handle {
+    self.node.handle()
+  }
+
+  pub fn addr(&self) -> &SocketAddr {
+    &self.addr
+  }
+
+  pub fn spawn(self) -> Handle {
+    self.node.spawn()
+  }
+}
+
+impl Node for TcpNode {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    TcpNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(TcpNode::spawn(self))
+  }
+}
+
+impl Source for TcpNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_input(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(f)
+  }
+}
+
+impl Sink for TcpNode {}
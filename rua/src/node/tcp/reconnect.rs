@@ -0,0 +1,443 @@
+use std::{
+  collections::{hash_map::RandomState, VecDeque},
+  future::Future,
+  hash::{BuildHasher, Hasher},
+  io::{self, ErrorKind},
+  net::SocketAddr,
+  time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::{
+  io::{AsyncWriteExt, BufReader},
+  net::{tcp::OwnedReadHalf, TcpStream},
+  sync::mpsc::{self, Receiver},
+  time,
+};
+
+use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
+  go,
+  model::{
+    CloseHandler, CloseReason, GeneralResult, Handle, HandleBuilder, HandleError, Handler,
+    StopPayload, StopRx, WritePayload, WriteRx,
+  },
+  node::{Node, Sink, Source},
+  take, take_mut,
+};
+
+/// A tcp client which keeps reconnecting to the server with exponential backoff and jitter.
+///
+/// The handle stays valid across reconnects.
+/// Writes issued while disconnected are buffered in the outbox and sent after reconnected,
+/// if the outbox is full, the write will fail with `HandleError::BufferFull`.
+pub struct ReconnectingTcpNode {
+  addr: String,
+  handle: Handle,
+  codec: Codec,
+  max_frame_len: usize,
+  rx: WriteRx,
+  stop_rx: StopRx,
+  input_handler: Option<Handler<Bytes>>,
+  connect_handler: Option<Box<dyn FnMut(SocketAddr) + Send>>,
+  disconnect_handler: Option<Box<dyn FnMut(CloseReason) + Send>>,
+  close_handler: Option<CloseHandler>,
+  connect_timeout_ms: Option<u64>,
+  min_retry_ms: u64,
+  max_retry_ms: u64,
+  outbox_size: usize,
+}
+
+impl ReconnectingTcpNode {
+  pub fn new(addr: &str, buffer: usize) -> Self {
+    let (tx, rx) = mpsc::channel(buffer);
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      addr: addr.to_string(),
+      handle: HandleBuilder::default()
+        .tx(tx)
+        .stop_tx(stop_tx)
+        .build()
+        .unwrap(),
+      codec: Codec::default(),
+      max_frame_len: DEFAULT_MAX_FRAME_LEN,
+      rx,
+      stop_rx,
+      input_handler: None,
+      connect_handler: None,
+      disconnect_handler: None,
+      close_handler: None,
+      connect_timeout_ms: None,
+      min_retry_ms: 100,
+      max_retry_ms: 30_000,
+      outbox_size: 64,
+    }
+  }
+
+  /// Set the codec used to frame messages. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.codec = codec;
+    self
+  }
+
+  /// Max length of a read frame, longer frames drop the connection.
+  /// Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn max_frame_len(mut self, len: usize) -> Self {
+    self.max_frame_len = len;
+    self
+  }
+
+  /// Timeout of each connection attempt. Default: no timeout.
+  pub fn connect_timeout_ms(mut self, ms: u64) -> Self {
+    self.connect_timeout_ms = Some(ms);
+    self
+  }
+
+  /// The retry delay starts from `min_ms` and doubles after each failed attempt, up to `max_ms`.
+  /// A random jitter of up to half the delay is subtracted from each delay.
+  /// Default: 100ms to 30s.
+  pub fn retry_ms(mut self, min_ms: u64, max_ms: u64) -> Self {
+    self.min_retry_ms = min_ms;
+    self.max_retry_ms = max_ms.max(min_ms);
+    self
+  }
+
+  /// Max count of writes buffered while disconnected. Default: 64.
+  pub fn outbox_size(mut self, size: usize) -> Self {
+    self.outbox_size = size;
+    self
+  }
+
+  pub fn on_input<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before reading the next frame.
+  pub fn on_input_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  /// Called with the server address after each successful connection.
+  pub fn on_connect<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(SocketAddr) + Send + 'static,
+  {
+    self.connect_handler = Some(Box::new(f));
+    self
+  }
+
+  /// Called with the reason when an established connection is lost, before reconnecting.
+  pub fn on_disconnect<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(CloseReason) + Send + 'static,
+  {
+    self.disconnect_handler = Some(Box::new(f));
+    self
+  }
+
+  /// Called once when the node is closed, which only happens when it's stopped
+  /// or all handles are dropped, so the reason is always `CloseReason::Stopped`.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+
+  pub fn spawn(self) -> Handle {
+    take!(
+      self,
+      addr,
+      codec,
+      max_frame_len,
+      connect_timeout_ms,
+      min_retry_ms,
+      max_retry_ms,
+      outbox_size,
+      close_handler
+    );
+    take_mut!(
+      self,
+      rx,
+      stop_rx,
+      input_handler,
+      connect_handler,
+      disconnect_handler
+    );
+
+    // supervisor thread
+    go! {
+      let mut outbox = Outbox::new(outbox_size);
+      let mut retry_ms = min_retry_ms;
+
+      loop {
+        let connect = connect(&addr, connect_timeout_ms);
+        let socket = match buffer_until(connect, &mut rx, &mut stop_rx, &mut outbox).await {
+          Wait::Done(Ok(socket)) => socket,
+          Wait::Done(Err(_)) => {
+            let delay = jitter(retry_ms);
+            retry_ms = (retry_ms.saturating_mul(2)).min(max_retry_ms);
+            match buffer_until(time::sleep(delay), &mut rx, &mut stop_rx, &mut outbox).await {
+              Wait::Done(()) => continue,
+              Wait::Stop(payload) => {
+                outbox.clear();
+                (payload.callback)(Ok(()));
+                break
+              }
+              Wait::Closed => break, // all tx are dropped
+            }
+          }
+          Wait::Stop(payload) => {
+            outbox.clear();
+            (payload.callback)(Ok(()));
+            break
+          }
+          Wait::Closed => break, // all tx are dropped
+        };
+
+        retry_ms = min_retry_ms;
+        if let Some(connect_handler) = connect_handler.as_mut() {
+          if let Ok(addr) = socket.peer_addr() {
+            connect_handler(addr);
+          }
+        }
+
+        let (reader, mut writer) = socket.into_split();
+        let (reader_stop_tx, reader_stop_rx) = mpsc::channel(1);
+
+        // reader thread, return the input handler when the connection is lost or stopped
+        let mut reader_task = tokio::spawn(read_loop(
+          reader,
+          codec,
+          max_frame_len,
+          input_handler.take(),
+          reader_stop_rx,
+        ));
+
+        let exit = loop {
+          let payload = if let Some(payload) = outbox.pop() {
+            payload
+          } else {
+            tokio::select! {
+              result = &mut reader_task => {
+                let (handler, reason) = result.unwrap_or((None, CloseReason::Eof));
+                input_handler = handler;
+                break Exit::Disconnected(reason)
+              }
+              Some(payload) = stop_rx.recv() => break Exit::Stop(payload),
+              payload = rx.recv() => {
+                if let Some(payload) = payload {
+                  payload
+                } else {
+                  break Exit::Closed // all tx are dropped
+                }
+              }
+            }
+          };
+
+          let result = async {
+            codec.write_frame(&mut writer, &payload.data).await?;
+            writer.flush().await?;
+            io::Result::Ok(())
+          }
+          .await;
+          if let Err(e) = result {
+            (payload.callback)(Err(Box::new(io::Error::new(e.kind(), e.to_string()))));
+            reader_stop_tx.send(()).await.ok();
+            input_handler = (&mut reader_task).await.map_or(None, |(handler, _)| handler);
+            break Exit::Disconnected(CloseReason::WriteError(e));
+          } else {
+            (payload.callback)(Ok(()));
+          }
+        };
+
+        match exit {
+          Exit::Disconnected(reason) => {
+            if let Some(disconnect_handler) = disconnect_handler.as_mut() {
+              disconnect_handler(reason);
+            }
+          }
+          Exit::Stop(payload) => {
+            reader_stop_tx.send(()).await.ok();
+            reader_task.await.ok();
+            outbox.clear();
+            (payload.callback)(Ok(()));
+            break
+          }
+          Exit::Closed => {
+            reader_stop_tx.send(()).await.ok();
+            reader_task.await.ok();
+            break
+          }
+        }
+      }
+      if let Some(close_handler) = close_handler {
+        close_handler(CloseReason::Stopped);
+      }
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
+    };
+
+    self.handle
+  }
+}
+
+impl Node for ReconnectingTcpNode {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    ReconnectingTcpNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(ReconnectingTcpNode::spawn(self))
+  }
+}
+
+impl Source for ReconnectingTcpNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_input(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(f)
+  }
+}
+
+impl Sink for ReconnectingTcpNode {}
+
+enum Wait<T> {
+  Done(T),
+  Stop(StopPayload),
+  Closed,
+}
+
+enum Exit {
+  Disconnected(CloseReason),
+  Stop(StopPayload),
+  Closed,
+}
+
+struct Outbox {
+  queue: VecDeque<WritePayload>,
+  size: usize,
+}
+
+impl Outbox {
+  fn new(size: usize) -> Self {
+    Self {
+      queue: VecDeque::new(),
+      size,
+    }
+  }
+
+  fn push(&mut self, payload: WritePayload) {
+    if self.queue.len() < self.size {
+      self.queue.push_back(payload);
+    } else {
+      (payload.callback)(Err(Box::new(HandleError::BufferFull)));
+    }
+  }
+
+  fn pop(&mut self) -> Option<WritePayload> {
+    self.queue.pop_front()
+  }
+
+  /// Fail all buffered writes.
+  fn clear(&mut self) {
+    for payload in self.queue.drain(..) {
+      (payload.callback)(Err(Box::new(HandleError::ChannelClosed)));
+    }
+  }
+}
+
+/// Wait for `fut` while buffering writes into the outbox.
+async fn buffer_until<F: Future>(
+  fut: F,
+  rx: &mut WriteRx,
+  stop_rx: &mut StopRx,
+  outbox: &mut Outbox,
+) -> Wait<F::Output> {
+  tokio::pin!(fut);
+  loop {
+    tokio::select! {
+      output = &mut fut => return Wait::Done(output),
+      Some(payload) = stop_rx.recv() => return Wait::Stop(payload),
+      payload = rx.recv() => {
+        if let Some(payload) = payload {
+          outbox.push(payload);
+        } else {
+          return Wait::Closed
+        }
+      }
+    }
+  }
+}
+
+async fn connect(addr: &str, timeout_ms: Option<u64>) -> io::Result<TcpStream> {
+  if let Some(timeout_ms) = timeout_ms {
+    match time::timeout(Duration::from_millis(timeout_ms), TcpStream::connect(addr)).await {
+      Ok(result) => result,
+      Err(_) => Err(io::Error::new(ErrorKind::TimedOut, "connect timeout")),
+    }
+  } else {
+    TcpStream::connect(addr).await
+  }
+}
+
+async fn read_loop(
+  reader: OwnedReadHalf,
+  codec: Codec,
+  max_frame_len: usize,
+  mut input_handler: Option<Handler<Bytes>>,
+  mut stop_rx: Receiver<()>,
+) -> (Option<Handler<Bytes>>, CloseReason) {
+  let mut reader = BufReader::new(reader);
+  let reason = loop {
+    tokio::select! {
+      Some(()) = stop_rx.recv() => break CloseReason::Stopped,
+      r = codec.read_frame(&mut reader, max_frame_len) => {
+        match r {
+          Ok(Some(data)) => {
+            if let Some(input_handler) = input_handler.as_mut() {
+              input_handler.call(data).await;
+            }
+          }
+          Ok(None) => break CloseReason::Eof,
+          Err(e) => break CloseReason::ReadError(e),
+        }
+      }
+    }
+  };
+  (input_handler, reason)
+}
+
+/// Subtract a random jitter of up to half of the delay.
+fn jitter(ms: u64) -> Duration {
+  let random = RandomState::new().build_hasher().finish();
+  let half = ms / 2;
+  Duration::from_millis(ms - half + random % (half + 1))
+}
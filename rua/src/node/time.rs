@@ -1,15 +1,16 @@
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
 use tokio::{sync::mpsc, time};
 
 use crate::{
   go,
-  model::{GeneralResult, HandleBuilder, StopOnlyHandle, StopRx},
+  model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx},
+  node::{Node, Source},
   take, take_mut, take_option_mut,
 };
 
 pub struct Ticker {
-  tick_handler: Option<Box<dyn FnMut(u64) + Send>>,
+  tick_handler: Option<Handler<u64>>,
   interval_ms: u64,
   stop_rx: StopRx,
   handle: StopOnlyHandle,
@@ -46,7 +47,17 @@ impl Ticker {
   }
 
   pub fn on_tick(mut self, f: impl FnMut(u64) + 'static + Send) -> Self {
-    self.tick_handler = Some(Box::new(f));
+    self.tick_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before the next tick, ticks missed meanwhile are fired immediately.
+  pub fn on_tick_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(u64) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.tick_handler = Some(Handler::new_async(f));
     self
   }
 
@@ -63,7 +74,7 @@ impl Ticker {
       loop {
         tokio::select! {
           _ = timer.tick() => {
-            (tick_handler)(current);
+            tick_handler.call(current).await;
             current += 1;
           }
           Some(payload) = stop_rx.recv() => {
@@ -76,3 +87,34 @@ impl Ticker {
     Ok(self.handle)
   }
 }
+
+impl Node for Ticker {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    Ticker::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    Ticker::spawn(self)
+  }
+}
+
+impl Source for Ticker {
+  type Output = u64;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(u64) + Send + 'static,
+  {
+    self.on_tick(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(u64) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_tick_async(f)
+  }
+}
@@ -0,0 +1,241 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::{
+  net,
+  sync::{mpsc, Semaphore},
+  time,
+};
+use tokio_rustls::{rustls::ServerConfig, TlsAcceptor, TlsStream};
+
+use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
+  go,
+  model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx},
+  node::{Node, Source},
+  take, take_mut, take_option_mut,
+};
+
+use super::TlsNode;
+
+/// TLS handshakes are done concurrently, so a slow client won't block other connections.
+/// Connections which failed the handshake will be dropped.
+pub struct TlsListener<'a> {
+  addr: &'a str,
+  cert_file: Option<&'a str>,
+  key_file: Option<&'a str>,
+  client_ca_file: Option<&'a str>,
+  server_config: Option<Arc<ServerConfig>>,
+  peer_handler: Option<Handler<TlsNode>>,
+  peer_write_buffer: usize,
+  peer_codec: Codec,
+  peer_max_frame_len: usize,
+  handshake_timeout_ms: u64,
+  max_pending_handshakes: usize,
+  handle: StopOnlyHandle,
+  stop_rx: StopRx,
+}
+
+impl<'a> TlsListener<'a> {
+  pub fn bind(addr: &'a str) -> Self {
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      addr,
+      cert_file: None,
+      key_file: None,
+      client_ca_file: None,
+      server_config: None,
+      stop_rx,
+      peer_handler: None,
+      peer_write_buffer: 16,
+      peer_codec: Codec::default(),
+      peer_max_frame_len: DEFAULT_MAX_FRAME_LEN,
+      handshake_timeout_ms: 10_000,
+      max_pending_handshakes: 64,
+      handle: HandleBuilder::default()
+        .stop_tx(stop_tx)
+        .build_stop_only()
+        .unwrap(),
+    }
+  }
+
+  /// PEM file of the server certificate chain.
+  pub fn cert_file(mut self, filename: &'a str) -> Self {
+    self.cert_file = Some(filename);
+    self
+  }
+
+  /// PEM file of the server private key.
+  pub fn key_file(mut self, filename: &'a str) -> Self {
+    self.key_file = Some(filename);
+    self
+  }
+
+  /// PEM file of the CAs used to verify client certificates.
+  /// If set, clients without a valid certificate will be rejected.
+  pub fn client_ca_file(mut self, filename: &'a str) -> Self {
+    self.client_ca_file = Some(filename);
+    self
+  }
+
+  /// Use a custom server config, `cert_file`, `key_file` and `client_ca_file` will be ignored.
+  pub fn server_config(mut self, config: Arc<ServerConfig>) -> Self {
+    self.server_config = Some(config);
+    self
+  }
+
+  pub fn peer_write_buffer(mut self, buffer: usize) -> Self {
+    self.peer_write_buffer = buffer;
+    self
+  }
+
+  /// Set the codec of new peers. Default: `Codec::lines()`.
+  pub fn peer_codec(mut self, codec: Codec) -> Self {
+    self.peer_codec = codec;
+    self
+  }
+
+  /// Set the max frame length of new peers. Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn peer_max_frame_len(mut self, len: usize) -> Self {
+    self.peer_max_frame_len = len;
+    self
+  }
+
+  /// Connections which can't finish the handshake in time will be dropped. Default: 10s.
+  pub fn handshake_timeout_ms(mut self, ms: u64) -> Self {
+    self.handshake_timeout_ms = ms;
+    self
+  }
+
+  /// Max count of concurrent handshakes, new connections won't be accepted until a handshake is done.
+  /// Default: 64.
+  pub fn max_pending_handshakes(mut self, n: usize) -> Self {
+    self.max_pending_handshakes = n.max(1);
+    self
+  }
+
+  pub fn on_new_peer(mut self, f: impl FnMut(TlsNode) + 'static + Send) -> Self {
+    self.peer_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before handling the next peer.
+  pub fn on_new_peer_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(TlsNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.peer_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  pub fn handle(&self) -> &StopOnlyHandle {
+    &self.handle
+  }
+
+  /// Return `Err` if missing `peer_handler`, missing certificate or key,
+  /// failed to load PEM files or failed bind to address.
+  pub async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    take_option_mut!(self, peer_handler);
+
+    let config = if let Some(config) = self.server_config {
+      config
+    } else {
+      let cert_file = self.cert_file.ok_or("missing cert_file")?;
+      let key_file = self.key_file.ok_or("missing key_file")?;
+      super::server_config(cert_file, key_file, self.client_ca_file)?
+    };
+    let acceptor = TlsAcceptor::from(config);
+
+    let listener = net::TcpListener::bind(self.addr).await?;
+
+    take!(
+      self,
+      peer_write_buffer,
+      peer_codec,
+      peer_max_frame_len,
+      handshake_timeout_ms,
+      max_pending_handshakes
+    );
+    take_mut!(self, stop_rx);
+
+    go! {
+      let (node_tx, mut node_rx) = mpsc::channel(16);
+      let semaphore = Arc::new(Semaphore::new(max_pending_handshakes));
+      let mut permit = None;
+
+      loop {
+        tokio::select! {
+          // only accept new connections when there is a free handshake slot
+          p = semaphore.clone().acquire_owned(), if permit.is_none() => {
+            permit = p.ok();
+          }
+          result = listener.accept(), if permit.is_some() => {
+            if let Ok((socket, addr)) = result {
+              let permit = permit.take();
+              let acceptor = acceptor.clone();
+              let node_tx = node_tx.clone();
+              // handshake thread
+              go! {
+                let handshake = time::timeout(
+                  Duration::from_millis(handshake_timeout_ms),
+                  acceptor.accept(socket),
+                );
+                let handshake = handshake.await;
+                drop(permit);
+                if let Ok(Ok(stream)) = handshake {
+                  let node = TlsNode::new(TlsStream::Server(stream), addr, peer_write_buffer)
+                    .codec(peer_codec)
+                    .max_frame_len(peer_max_frame_len);
+                  node_tx.send(node).await.ok();
+                }
+              };
+            } else {
+              break
+            }
+          }
+          Some(node) = node_rx.recv() => {
+            peer_handler.call(node).await;
+          }
+          Some(payload) = stop_rx.recv() => {
+            (payload.callback)(Ok(()));
+            break
+          }
+        }
+      }
+    };
+
+    Ok(self.handle)
+  }
+}
+
+impl<'a> Node for TlsListener<'a> {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    TlsListener::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    TlsListener::spawn(self).await
+  }
+}
+
+impl<'a> Source for TlsListener<'a> {
+  type Output = TlsNode;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(TlsNode) + Send + 'static,
+  {
+    self.on_new_peer(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(TlsNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_new_peer_async(f)
+  }
+}
@@ -0,0 +1,79 @@
+//! TLS transport based on rustls. Enabled by the `tls` feature.
+
+pub mod listener;
+pub mod node;
+
+pub use listener::TlsListener;
+pub use node::TlsNode;
+pub use tokio_rustls::rustls;
+
+use std::sync::Arc;
+
+use rustls::{
+  pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+  server::WebPkiClientVerifier,
+  ClientConfig, RootCertStore, ServerConfig,
+};
+
+use crate::model::GeneralResult;
+
+/// Load all certificates from a PEM file.
+pub fn load_certs(filename: &str) -> GeneralResult<Vec<CertificateDer<'static>>> {
+  Ok(CertificateDer::pem_file_iter(filename)?.collect::<Result<_, _>>()?)
+}
+
+/// Load the first private key from a PEM file.
+pub fn load_private_key(filename: &str) -> GeneralResult<PrivateKeyDer<'static>> {
+  Ok(PrivateKeyDer::from_pem_file(filename)?)
+}
+
+/// Load all certificates from a PEM file into a root store.
+pub fn load_root_store(filename: &str) -> GeneralResult<RootCertStore> {
+  let mut roots = RootCertStore::empty();
+  for cert in load_certs(filename)? {
+    roots.add(cert)?;
+  }
+  Ok(roots)
+}
+
+/// Build a server config from PEM files.
+/// If `client_ca_file` is provided, clients must present a certificate signed by one of its CAs.
+pub fn server_config(
+  cert_file: &str,
+  key_file: &str,
+  client_ca_file: Option<&str>,
+) -> GeneralResult<Arc<ServerConfig>> {
+  let builder = if let Some(client_ca_file) = client_ca_file {
+    let verifier =
+      WebPkiClientVerifier::builder(Arc::new(load_root_store(client_ca_file)?)).build()?;
+    ServerConfig::builder().with_client_cert_verifier(verifier)
+  } else {
+    ServerConfig::builder().with_no_client_auth()
+  };
+  Ok(Arc::new(builder.with_single_cert(
+    load_certs(cert_file)?,
+    load_private_key(key_file)?,
+  )?))
+}
+
+/// Build a client config trusting the CAs in the PEM file.
+pub fn client_config(ca_file: &str) -> GeneralResult<Arc<ClientConfig>> {
+  Ok(Arc::new(
+    ClientConfig::builder()
+      .with_root_certificates(load_root_store(ca_file)?)
+      .with_no_client_auth(),
+  ))
+}
+
+/// Build a client config trusting the CAs in the PEM file, and present a client certificate.
+pub fn client_auth_config(
+  ca_file: &str,
+  cert_file: &str,
+  key_file: &str,
+) -> GeneralResult<Arc<ClientConfig>> {
+  Ok(Arc::new(
+    ClientConfig::builder()
+      .with_root_certificates(load_root_store(ca_file)?)
+      .with_client_auth_cert(load_certs(cert_file)?, load_private_key(key_file)?)?,
+  ))
+}
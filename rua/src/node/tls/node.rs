@@ -0,0 +1,165 @@
+use std::{convert::TryFrom, future::Future, net::SocketAddr, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use tokio::{net::TcpStream, time};
+use tokio_rustls::{
+  rustls::{
+    pki_types::{CertificateDer, ServerName},
+    ClientConfig,
+  },
+  TlsConnector, TlsStream,
+};
+
+use crate::{
+  codec::Codec,
+  model::{CloseReason, GeneralResult, Handle, HandleError},
+  node::{stream::StreamNode, Node, Sink, Source},
+};
+
+pub struct TlsNode {
+  node: StreamNode,
+  addr: SocketAddr,
+  peer_certificates: Option<Vec<CertificateDer<'static>>>,
+}
+
+impl TlsNode {
+  pub fn new(stream: TlsStream<TcpStream>, addr: SocketAddr, buffer: usize) -> Self {
+    let peer_certificates = stream.get_ref().1.peer_certificates().map(|c| c.to_vec());
+    Self {
+      node: StreamNode::new(stream, buffer),
+      addr,
+      peer_certificates,
+    }
+  }
+
+  /// Connect to a remote address as a client.
+  /// `server_name` is used to verify the server certificate.
+  ///
+  /// Return `Err` if failed to connect or the handshake failed.
+  pub async fn connect(
+    addr: &str,
+    server_name: &str,
+    config: Arc<ClientConfig>,
+    buffer: usize,
+  ) -> GeneralResult<Self> {
+    let server_name = ServerName::try_from(server_name.to_string())?;
+    let socket = TcpStream::connect(addr).await?;
+    let addr = socket.peer_addr()?;
+    let stream = TlsConnector::from(config)
+      .connect(server_name, socket)
+      .await?;
+    Ok(Self::new(TlsStream::Client(stream), addr, buffer))
+  }
+
+  /// Connect to a remote address as a client.
+  /// `server_name` is used to verify the server certificate.
+  ///
+  /// Return `Err` if failed to connect, the handshake failed or timeout.
+  pub async fn timed_connect(
+    addr: &str,
+    server_name: &str,
+    config: Arc<ClientConfig>,
+    buffer: usize,
+    timeout_ms: u64,
+  ) -> GeneralResult<Self> {
+    match time::timeout(
+      Duration::from_millis(timeout_ms),
+      Self::connect(addr, server_name, config, buffer),
+    )
+    .await
+    {
+      Ok(result) => result,
+      Err(_) => Err(Box::new(HandleError::Timeout)),
+    }
+  }
+
+  /// Set the codec used to frame messages. Default: `Codec::lines()`.
+  pub fn codec(mut self, codec: Codec) -> Self {
+    self.node = self.node.codec(codec);
+    self
+  }
+
+  /// Max length of a read frame, longer frames close the node with `InvalidData`.
+  /// Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn max_frame_len(mut self, len: usize) -> Self {
+    self.node = self.node.max_frame_len(len);
+    self
+  }
+
+  pub fn on_input<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.node = self.node.on_input(f);
+    self
+  }
+
+  /// The handler is awaited before reading the next frame.
+  pub fn on_input_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.node = self.node.on_input_async(f);
+    self
+  }
+
+  /// Called once when the node is closed, see `StreamNode::on_close`.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.node = self.node.on_close(f);
+    self
+  }
+
+  pub fn handle(&self) -> &Handle {
+    self.node.handle()
+  }
+
+  pub fn addr(&self) -> &SocketAddr {
+    &self.addr
+  }
+
+  /// Certificates presented by the peer, if any.
+  pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+    self.peer_certificates.as_deref()
+  }
+
+  pub fn spawn(self) -> Handle {
+    self.node.spawn()
+  }
+}
+
+impl Node for TlsNode {
+  type Handle = Handle;
+
+  fn handle(&self) -> &Handle {
+    TlsNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<Handle> {
+    Ok(TlsNode::spawn(self))
+  }
+}
+
+impl Source for TlsNode {
+  type Output = Bytes;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) + Send + 'static,
+  {
+    self.on_input(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(f)
+  }
+}
+
+impl Sink for TlsNode {}
@@ -0,0 +1,78 @@
+use std::{
+  future::Future,
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, Receiver};
+
+use crate::model::{GeneralResult, Handle, Stop};
+
+/// Node is implemented by all nodes, so generic code can work with arbitrary nodes,
+/// e.g. to supervise, register or test them.
+///
+/// Nodes also have their own `spawn` methods, which are simpler to use if the type is known.
+pub trait Node: Sized + Send {
+  /// The handle returned by `spawn`.
+  type Handle: Stop + Clone + Send + 'static;
+
+  /// The handle is available before the node is spawned.
+  fn handle(&self) -> &Self::Handle;
+
+  /// Return `Err` if the node can't be spawned, e.g. failed to bind an address or open a file.
+  fn spawn(self) -> impl Future<Output = GeneralResult<Self::Handle>> + Send;
+}
+
+/// Source nodes produce data, e.g. lines from stdin, ticks or new peers of a listener.
+pub trait Source: Node {
+  type Output: Send + 'static;
+
+  /// Set the handler of produced data, e.g. `on_input` of `StdioNode` or `on_tick` of `Ticker`.
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(Self::Output) + Send + 'static;
+
+  /// Set the async handler of produced data, e.g. `on_input_async` of `StdioNode`.
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(Self::Output) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static;
+
+  /// Spawn the node and receive the produced data from a stream instead of a handler.
+  /// The node waits if the stream is not consumed in time and `buffer` is full.
+  ///
+  /// Dropping the stream doesn't stop the node, use the returned handle.
+  fn spawn_stream(
+    self,
+    buffer: usize,
+  ) -> impl Future<Output = GeneralResult<(Self::Handle, OutputStream<Self::Output>)>> + Send {
+    let (tx, rx) = mpsc::channel(buffer);
+    let node = self.on_output_async(move |data| {
+      let tx = tx.clone();
+      async move {
+        tx.send(data).await.ok();
+      }
+    });
+    async move {
+      let handle = node.spawn().await?;
+      Ok((handle, OutputStream { rx }))
+    }
+  }
+}
+
+/// Sink nodes consume data written by their `Handle`, e.g. `FileNode` or `TcpNode`.
+pub trait Sink: Node<Handle = Handle> {}
+
+/// The output of a source node, see `Source::spawn_stream`.
+pub struct OutputStream<T> {
+  rx: Receiver<T>,
+}
+
+impl<T> Stream for OutputStream<T> {
+  type Item = T;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+    self.rx.poll_recv(cx)
+  }
+}
@@ -0,0 +1,337 @@
+use std::{
+  future::Future,
+  net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+  sync::Arc,
+};
+
+use bytes::Bytes;
+use tokio::{
+  net::{self, UdpSocket},
+  sync::mpsc,
+};
+
+use crate::{
+  go,
+  model::{
+    CloseHandler, CloseReason, GeneralResult, Handle, HandleBuilder, Handler, OverflowPolicy, Stop,
+    StopRx, WriteRx, WriteTx,
+  },
+  node::{Node, Source},
+  take, take_mut,
+};
+
+pub type WriteToTx = WriteTx<(SocketAddr, Bytes)>;
+pub type WriteToRx = WriteRx<(SocketAddr, Bytes)>;
+
+/// UdpNode sends and receives datagrams, each datagram is a message.
+pub struct UdpNode<'a> {
+  addr: &'a str,
+  default_peer: Option<&'a str>,
+  multicast_v4: Vec<(Ipv4Addr, Ipv4Addr)>,
+  multicast_v6: Vec<(Ipv6Addr, u32)>,
+  max_datagram_size: usize,
+  input_handler: Option<Handler<(SocketAddr, Bytes)>>,
+  close_handler: Option<CloseHandler>,
+  handle: UdpHandle,
+  rx: WriteRx,
+  to_rx: WriteToRx,
+  stop_rx: StopRx,
+}
+
+impl<'a> UdpNode<'a> {
+  pub fn bind(addr: &'a str) -> Self {
+    Self::new(addr, 16)
+  }
+
+  pub fn new(addr: &'a str, buffer: usize) -> Self {
+    let (tx, rx) = mpsc::channel(buffer);
+    let (to_tx, to_rx) = mpsc::channel(buffer);
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      addr,
+      default_peer: None,
+      multicast_v4: Vec::new(),
+      multicast_v6: Vec::new(),
+      max_datagram_size: 65507,
+      input_handler: None,
+      close_handler: None,
+      handle: UdpHandle {
+        handle: HandleBuilder::default()
+          .tx(tx)
+          .stop_tx(stop_tx.clone())
+          .build()
+          .unwrap(),
+        to_handle: HandleBuilder::new()
+          .tx(to_tx)
+          .stop_tx(stop_tx)
+          .build()
+          .unwrap(),
+      },
+      rx,
+      to_rx,
+      stop_rx,
+    }
+  }
+
+  /// Destination of `UdpHandle::write` and the `Handle` of this node.
+  /// Without a default peer, these writes will fail.
+  pub fn default_peer(mut self, addr: &'a str) -> Self {
+    self.default_peer = Some(addr);
+    self
+  }
+
+  /// Join an IPv4 multicast group on the interface. Use `Ipv4Addr::UNSPECIFIED` to let the OS choose the interface.
+  pub fn join_multicast_v4(mut self, group: Ipv4Addr, interface: Ipv4Addr) -> Self {
+    self.multicast_v4.push((group, interface));
+    self
+  }
+
+  /// Join an IPv6 multicast group on the interface. Use `0` to let the OS choose the interface.
+  pub fn join_multicast_v6(mut self, group: Ipv6Addr, interface: u32) -> Self {
+    self.multicast_v6.push((group, interface));
+    self
+  }
+
+  /// Datagrams larger than this will be truncated. Default: 65507.
+  pub fn max_datagram_size(mut self, size: usize) -> Self {
+    self.max_datagram_size = size;
+    self
+  }
+
+  pub fn on_input<F>(mut self, mut f: F) -> Self
+  where
+    F: FnMut(SocketAddr, Bytes) + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_sync(move |(addr, data)| f(addr, data)));
+    self
+  }
+
+  /// The handler is awaited before receiving the next datagram.
+  pub fn on_input_async<F, Fut>(mut self, mut f: F) -> Self
+  where
+    F: FnMut(SocketAddr, Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.input_handler = Some(Handler::new_async(move |(addr, data)| f(addr, data)));
+    self
+  }
+
+  /// Called once when the node is stopped, the reason is always `CloseReason::Stopped`.
+  /// Receive errors don't close the node, and it keeps receiving after all handles are dropped.
+  pub fn on_close<F>(mut self, f: F) -> Self
+  where
+    F: FnOnce(CloseReason) + Send + 'static,
+  {
+    self.close_handler = Some(Box::new(f));
+    self
+  }
+
+  pub fn handle(&self) -> &UdpHandle {
+    &self.handle
+  }
+
+  /// Return `Err` if failed to bind address, resolve the default peer or join multicast groups.
+  pub async fn spawn(self) -> GeneralResult<UdpHandle> {
+    let socket = UdpSocket::bind(self.addr).await?;
+    for (group, interface) in &self.multicast_v4 {
+      socket.join_multicast_v4(*group, *interface)?;
+    }
+    for (group, interface) in &self.multicast_v6 {
+      socket.join_multicast_v6(group, *interface)?;
+    }
+    let default_peer = if let Some(addr) = self.default_peer {
+      Some(
+        net::lookup_host(addr)
+          .await?
+          .next()
+          .ok_or("failed to resolve default peer")?,
+      )
+    } else {
+      None
+    };
+
+    take!(self, max_datagram_size, close_handler);
+    take_mut!(self, rx, to_rx, stop_rx, input_handler);
+
+    let socket = Arc::new(socket);
+    let (reader_stop_tx, mut reader_stop_rx) = mpsc::channel(1);
+
+    // reader thread, writes are handled by the writer thread,
+    // so an async input handler can await writes of this node
+    let reader = socket.clone();
+    go! {
+      let mut buf = vec![0; max_datagram_size];
+      loop {
+        tokio::select! {
+          Some(()) = reader_stop_rx.recv() => break,
+          r = reader.recv_from(&mut buf) => {
+            // errors like ICMP port unreachable of a previous datagram are ignored
+            if let Ok((n, addr)) = r {
+              if let Some(input_handler) = input_handler.as_mut() {
+                input_handler.call((addr, Bytes::copy_from_slice(&buf[..n]))).await;
+              }
+            }
+          }
+        }
+      }
+    };
+
+    // writer thread
+    go! {
+      loop {
+        tokio::select! {
+          Some(payload) = stop_rx.recv() => {
+            reader_stop_tx.send(()).await.ok();
+            if let Some(close_handler) = close_handler {
+              close_handler(CloseReason::Stopped);
+            }
+            (payload.callback)(Ok(()));
+            break
+          }
+          Some(payload) = rx.recv() => {
+            if let Some(addr) = default_peer {
+              match socket.send_to(&payload.data, addr).await {
+                Ok(_) => (payload.callback)(Ok(())),
+                Err(e) => (payload.callback)(Err(Box::new(e))),
+              }
+            } else {
+              (payload.callback)(Err("missing default peer".into()));
+            }
+          }
+          Some(payload) = to_rx.recv() => {
+            let (addr, data) = &payload.data;
+            match socket.send_to(data, addr).await {
+              Ok(_) => (payload.callback)(Ok(())),
+              Err(e) => (payload.callback)(Err(Box::new(e))),
+            }
+          }
+          // all tx are dropped, keep receiving
+          else => break,
+        }
+      }
+      // stop_rx is dropped, later stop_tx.send will throw ChannelClosed error.
+    };
+
+    Ok(self.handle)
+  }
+}
+
+impl<'a> Node for UdpNode<'a> {
+  type Handle = UdpHandle;
+
+  fn handle(&self) -> &UdpHandle {
+    UdpNode::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<UdpHandle> {
+    UdpNode::spawn(self).await
+  }
+}
+
+impl<'a> Source for UdpNode<'a> {
+  type Output = (SocketAddr, Bytes);
+
+  fn on_output<F>(self, mut f: F) -> Self
+  where
+    F: FnMut((SocketAddr, Bytes)) + Send + 'static,
+  {
+    self.on_input(move |addr, data| f((addr, data)))
+  }
+
+  fn on_output_async<F, Fut>(self, mut f: F) -> Self
+  where
+    F: FnMut((SocketAddr, Bytes)) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_input_async(move |addr, data| f((addr, data)))
+  }
+}
+
+/// UdpHandle can write to the default peer like a `Handle`, or write to an explicit address.
+#[derive(Clone)]
+pub struct UdpHandle {
+  handle: Handle,
+  to_handle: Handle<(SocketAddr, Bytes)>,
+}
+
+impl UdpHandle {
+  /// Set the timeout of writes to the default peer and to explicit addresses.
+  pub fn set_timeout_ms(&mut self, ms: u64) {
+    self.handle.set_timeout_ms(ms);
+    self.to_handle.set_timeout_ms(ms);
+  }
+
+  pub fn clear_timeout(&mut self) {
+    self.handle.clear_timeout();
+    self.to_handle.clear_timeout();
+  }
+
+  /// Set the overflow policy of writes to the default peer and to explicit addresses.
+  pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+    self.handle.set_overflow(policy);
+    self.to_handle.set_overflow(policy);
+  }
+
+  pub fn overflow(&self) -> OverflowPolicy {
+    self.to_handle.overflow()
+  }
+
+  /// Count of writes discarded because the node's buffer was full, see `Handle::dropped`.
+  pub fn dropped(&self) -> u64 {
+    self.handle.dropped() + self.to_handle.dropped()
+  }
+
+  /// Write to the default peer.
+  pub fn write(&self, data: Bytes) {
+    self.handle.write(data)
+  }
+
+  /// Write to the default peer.
+  pub fn write_then<F>(&self, data: Bytes, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.handle.write_then(data, callback)
+  }
+
+  pub fn write_to(&self, addr: SocketAddr, data: Bytes) {
+    self.write_to_then(addr, data, |_| {})
+  }
+
+  pub fn write_to_then<F>(&self, addr: SocketAddr, data: Bytes, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.to_handle.write_then((addr, data), callback)
+  }
+
+  pub fn stop(self) {
+    self.handle.stop()
+  }
+
+  pub fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.handle.stop_then(callback)
+  }
+
+  /// The handle which writes to the default peer, useful with `Broadcaster`.
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+}
+
+impl Stop for UdpHandle {
+  fn stop(self) {
+    UdpHandle::stop(self)
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    UdpHandle::stop_then(self, callback)
+  }
+}
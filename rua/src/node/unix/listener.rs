@@ -0,0 +1,185 @@
+use std::{
+  future::Future,
+  io::{self, ErrorKind},
+  os::unix::fs::FileTypeExt,
+  path::{Path, PathBuf},
+};
+
+use tokio::{fs, net, sync::mpsc};
+
+use crate::{
+  codec::{Codec, DEFAULT_MAX_FRAME_LEN},
+  go,
+  model::{GeneralResult, HandleBuilder, Handler, StopOnlyHandle, StopRx},
+  node::{Node, Source},
+  take, take_mut, take_option_mut,
+};
+
+use super::UnixNode;
+
+/// A stale socket file at the path will be removed before binding,
+/// and the socket file will be removed after the listener is stopped.
+pub struct UnixListener {
+  path: PathBuf,
+  peer_handler: Option<Handler<UnixNode>>,
+  peer_write_buffer: usize,
+  peer_codec: Codec,
+  peer_max_frame_len: usize,
+  handle: StopOnlyHandle,
+  stop_rx: StopRx,
+}
+
+impl UnixListener {
+  pub fn bind<P: AsRef<Path>>(path: P) -> Self {
+    let (stop_tx, stop_rx) = mpsc::channel(1);
+
+    Self {
+      path: path.as_ref().to_path_buf(),
+      stop_rx,
+      peer_handler: None,
+      peer_write_buffer: 16,
+      peer_codec: Codec::default(),
+      peer_max_frame_len: DEFAULT_MAX_FRAME_LEN,
+      handle: HandleBuilder::default()
+        .stop_tx(stop_tx)
+        .build_stop_only()
+        .unwrap(),
+    }
+  }
+
+  pub fn peer_write_buffer(mut self, buffer: usize) -> Self {
+    self.peer_write_buffer = buffer;
+    self
+  }
+
+  /// Set the codec of new peers. Default: `Codec::lines()`.
+  pub fn peer_codec(mut self, codec: Codec) -> Self {
+    self.peer_codec = codec;
+    self
+  }
+
+  /// Set the max frame length of new peers. Default: `DEFAULT_MAX_FRAME_LEN`.
+  pub fn peer_max_frame_len(mut self, len: usize) -> Self {
+    self.peer_max_frame_len = len;
+    self
+  }
+
+  /// Peer credentials are available by `UnixNode::cred`.
+  pub fn on_new_peer(mut self, f: impl FnMut(UnixNode) + 'static + Send) -> Self {
+    self.peer_handler = Some(Handler::new_sync(f));
+    self
+  }
+
+  /// The handler is awaited before handling the next peer.
+  pub fn on_new_peer_async<F, Fut>(mut self, f: F) -> Self
+  where
+    F: FnMut(UnixNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.peer_handler = Some(Handler::new_async(f));
+    self
+  }
+
+  pub fn handle(&self) -> &StopOnlyHandle {
+    &self.handle
+  }
+
+  /// Return `Err` if missing `peer_handler`, the path is in use or failed bind to the path.
+  pub async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    take_option_mut!(self, peer_handler);
+
+    remove_stale_socket(&self.path).await?;
+    let listener = net::UnixListener::bind(&self.path)?;
+
+    take!(
+      self,
+      path,
+      peer_write_buffer,
+      peer_codec,
+      peer_max_frame_len
+    );
+    take_mut!(self, stop_rx);
+
+    go! {
+      loop {
+        tokio::select! {
+          result = listener.accept() => {
+            if let Ok((socket, _)) = result {
+              let node = UnixNode::new(socket, peer_write_buffer)
+                .codec(peer_codec)
+                .max_frame_len(peer_max_frame_len);
+              peer_handler.call(node).await;
+            } else {
+              break
+            }
+          }
+          Some(payload) = stop_rx.recv() => {
+            drop(listener);
+            fs::remove_file(&path).await.ok();
+            (payload.callback)(Ok(()));
+            return
+          }
+        }
+      }
+      fs::remove_file(&path).await.ok();
+    };
+
+    Ok(self.handle)
+  }
+}
+
+impl Node for UnixListener {
+  type Handle = StopOnlyHandle;
+
+  fn handle(&self) -> &StopOnlyHandle {
+    UnixListener::handle(self)
+  }
+
+  async fn spawn(self) -> GeneralResult<StopOnlyHandle> {
+    UnixListener::spawn(self).await
+  }
+}
+
+impl Source for UnixListener {
+  type Output = UnixNode;
+
+  fn on_output<F>(self, f: F) -> Self
+  where
+    F: FnMut(UnixNode) + Send + 'static,
+  {
+    self.on_new_peer(f)
+  }
+
+  fn on_output_async<F, Fut>(self, f: F) -> Self
+  where
+    F: FnMut(UnixNode) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_new_peer_async(f)
+  }
+}
+
+/// Remove the socket file if no one is listening on it.
+async fn remove_stale_socket(path: &Path) -> io::Result<()> {
+  match fs::symlink_metadata(path).await {
+    Ok(metadata) => {
+      if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+          ErrorKind::AlreadyExists,
+          "path exists and is not a socket",
+        ));
+      }
+      match net::UnixStream::connect(path).await {
+        Ok(_) => Err(io::Error::new(
+          ErrorKind::AddrInUse,
+          "socket is in use by another listener",
+        )),
+        // nobody is listening, other errors like `PermissionDenied` may be a live listener
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => fs::remove_file(path).await,
+        Err(e) => Err(e),
+      }
+    }
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+    Err(e) => Err(e),
+  }
+}
@@ -0,0 +1,5 @@
+pub mod listener;
+pub mod node;
+
+pub use listener::UnixListener;
+pub use node::UnixNode;
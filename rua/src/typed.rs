@@ -0,0 +1,274 @@
+use std::{error::Error, fmt, future::Future, marker::PhantomData};
+
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::model::{BoxFuture, GeneralResult, Handle, HandleError, Stop};
+
+/// Serialization format of typed messages.
+///
+/// MessagePack and CBOR are binary, and may contain newlines,
+/// so use them with a length-prefixed codec, e.g. `Codec::U32Prefixed`.
+/// JSON is encoded without newlines, so it works with `Codec::lines()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  Json,
+  /// Structs are encoded as maps with field names.
+  MessagePack,
+  Cbor,
+}
+
+impl Format {
+  pub fn encode<T: Serialize + ?Sized>(&self, msg: &T) -> Result<Bytes, FormatError> {
+    let result = match self {
+      Format::Json => serde_json::to_vec(msg).map_err(|e| e.to_string()),
+      Format::MessagePack => rmp_serde::to_vec_named(msg).map_err(|e| e.to_string()),
+      Format::Cbor => {
+        let mut buf = Vec::new();
+        ciborium::into_writer(msg, &mut buf)
+          .map(|_| buf)
+          .map_err(|e| e.to_string())
+      }
+    };
+    result.map(Bytes::from).map_err(FormatError::Encode)
+  }
+
+  pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError> {
+    let result = match self {
+      Format::Json => serde_json::from_slice(data).map_err(|e| e.to_string()),
+      Format::MessagePack => rmp_serde::from_slice(data).map_err(|e| e.to_string()),
+      Format::Cbor => ciborium::from_reader(data).map_err(|e| e.to_string()),
+    };
+    result.map_err(FormatError::Decode)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+  Encode(String),
+  Decode(String),
+}
+
+impl fmt::Display for FormatError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FormatError::Encode(e) => write!(f, "failed to encode message: {}", e),
+      FormatError::Decode(e) => write!(f, "failed to decode message: {}", e),
+    }
+  }
+}
+
+impl Error for FormatError {}
+
+type DecodeErrorFn = Box<dyn FnMut(FormatError, &[u8]) + Send>;
+
+/// Decoder turns a handler of `T` into a handler of raw input, e.g. for `on_input` of `TcpNode`.
+///
+/// The input can be anything viewed as bytes, e.g. `Bytes` or `WsMessage`.
+pub struct Decoder<T> {
+  format: Format,
+  error_handler: Option<DecodeErrorFn>,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned + 'static> Decoder<T> {
+  pub fn new(format: Format) -> Self {
+    Self {
+      format,
+      error_handler: None,
+      phantom: PhantomData,
+    }
+  }
+
+  /// Called with the error and the raw input if the input can't be decoded.
+  /// Default: invalid input is discarded.
+  pub fn on_error<F>(mut self, f: F) -> Self
+  where
+    F: FnMut(FormatError, &[u8]) + Send + 'static,
+  {
+    self.error_handler = Some(Box::new(f));
+    self
+  }
+
+  /// Decode the input, return `None` and call the error handler if failed.
+  pub fn decode(&mut self, data: &[u8]) -> Option<T> {
+    match self.format.decode(data) {
+      Ok(msg) => Some(msg),
+      Err(e) => {
+        if let Some(error_handler) = self.error_handler.as_mut() {
+          error_handler(e, data);
+        }
+        None
+      }
+    }
+  }
+
+  pub fn handler<I, F>(mut self, mut f: F) -> impl FnMut(I) + Send + 'static
+  where
+    I: AsRef<[u8]>,
+    F: FnMut(T) + Send + 'static,
+  {
+    move |data| {
+      if let Some(msg) = self.decode(data.as_ref()) {
+        f(msg)
+      }
+    }
+  }
+
+  /// Invalid input resolves immediately after the error handler is called.
+  pub fn async_handler<I, F, Fut>(mut self, mut f: F) -> impl FnMut(I) -> BoxFuture + Send + 'static
+  where
+    I: AsRef<[u8]>,
+    F: FnMut(T) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    move |data| match self.decode(data.as_ref()) {
+      Some(msg) => Box::pin(f(msg)),
+      None => Box::pin(async {}),
+    }
+  }
+}
+
+/// TypedHandle encodes messages before writing them with the inner handle.
+pub struct TypedHandle<T> {
+  handle: Handle,
+  format: Format,
+  phantom: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for TypedHandle<T> {
+  fn clone(&self) -> Self {
+    Self {
+      handle: self.handle.clone(),
+      format: self.format,
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T: Serialize> TypedHandle<T> {
+  pub fn new(handle: Handle, format: Format) -> Self {
+    Self {
+      handle,
+      format,
+      phantom: PhantomData,
+    }
+  }
+
+  /// Return `Err` if the message can't be encoded, nothing is written in this case.
+  pub fn write(&self, msg: &T) -> Result<(), FormatError> {
+    self.handle.write(self.format.encode(msg)?);
+    Ok(())
+  }
+
+  /// The callback is called with `FormatError` if the message can't be encoded.
+  pub fn write_then<F>(&self, msg: &T, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    match self.format.encode(msg) {
+      Ok(data) => self.handle.write_then(data, callback),
+      Err(e) => callback(Err(Box::new(e))),
+    }
+  }
+
+  /// Return `HandleError::WriteFailed` if the message can't be encoded.
+  pub async fn send(&self, msg: &T) -> Result<(), HandleError> {
+    let data = self
+      .format
+      .encode(msg)
+      .map_err(|e| HandleError::WriteFailed(e.to_string()))?;
+    self.handle.send(data).await
+  }
+
+  pub fn handle(&self) -> &Handle {
+    &self.handle
+  }
+
+  pub fn into_inner(self) -> Handle {
+    self.handle
+  }
+}
+
+impl<T> Stop for TypedHandle<T> {
+  fn stop(self) {
+    self.handle.stop()
+  }
+
+  fn stop_then<F>(self, callback: F)
+  where
+    F: Fn(GeneralResult<()>) + Send + Clone + Sync + 'static,
+  {
+    self.handle.stop_then(callback)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use serde::Deserialize;
+
+  use super::*;
+
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct Msg {
+    id: u32,
+    text: String,
+    tags: Vec<String>,
+  }
+
+  fn msg() -> Msg {
+    Msg {
+      id: 7,
+      text: "multi\nline\r\n".to_string(),
+      tags: vec!["a".to_string(), "b\n".to_string()],
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    for format in [Format::Json, Format::MessagePack, Format::Cbor] {
+      let data = format.encode(&msg()).unwrap();
+      assert_eq!(format.decode::<Msg>(&data).unwrap(), msg(), "{:?}", format);
+    }
+  }
+
+  #[test]
+  fn json_has_no_newline() {
+    let data = Format::Json.encode(&msg()).unwrap();
+    assert!(!data.contains(&b'\n'));
+  }
+
+  #[test]
+  fn decode_error() {
+    for format in [Format::Json, Format::MessagePack, Format::Cbor] {
+      let errors = Arc::new(Mutex::new(Vec::new()));
+      let mut decoder = Decoder::<Msg>::new(format).on_error({
+        let errors = errors.clone();
+        move |e, data| errors.lock().unwrap().push((e, data.to_vec()))
+      });
+
+      let valid = format.encode(&msg()).unwrap();
+      assert_eq!(decoder.decode(&valid), Some(msg()));
+      assert!(decoder.decode(b"\xffnot a message").is_none());
+
+      let errors = errors.lock().unwrap();
+      assert_eq!(errors.len(), 1, "{:?}", format);
+      assert!(matches!(errors[0].0, FormatError::Decode(_)));
+      assert_eq!(errors[0].1, b"\xffnot a message");
+    }
+  }
+
+  #[test]
+  fn handler_skips_invalid_input() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let mut handler = Decoder::<Msg>::new(Format::Json).handler({
+      let received = received.clone();
+      move |msg| received.lock().unwrap().push(msg)
+    });
+    handler(Bytes::from_static(b"{"));
+    handler(Format::Json.encode(&msg()).unwrap());
+    assert_eq!(*received.lock().unwrap(), [msg()]);
+  }
+}